@@ -0,0 +1,45 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[allow(dead_code)]
+#[path = "src/intcode"]
+mod intcode {
+    pub type Word = i128;
+
+    pub mod disasm;
+    pub mod isa;
+    pub mod transpile;
+}
+
+use intcode::transpile::Transpiler;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // The transpiled programs are only used by the transpiler's tests
+    if env::var_os("CARGO_FEATURE_TRANSPILED").is_none() {
+        return;
+    }
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+
+    for &(day, name) in [(5, "Day5"), (9, "Day9"), (19, "Day19")].iter() {
+        let input = format!("res/{}.txt", day);
+        println!("cargo:rerun-if-changed={}", input);
+
+        let program = match fs::read_to_string(&input) {
+            Ok(program) => program,
+            Err(e) => {
+                println!("cargo:warning=not transpiling {}: {}", input, e);
+                continue;
+            }
+        };
+        let code = Transpiler::new(name).transpile(&program);
+        fs::write(Path::new(&out_dir).join(format!("day{}.rs", day)), code).unwrap();
+    }
+
+    for src in ["disasm", "isa", "transpile"].iter() {
+        println!("cargo:rerun-if-changed=src/intcode/{}.rs", src);
+    }
+}
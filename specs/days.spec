@@ -0,0 +1,31 @@
+# Regression cases for the puzzle inputs.
+
+[day 2, noun 12 verb 2]
+program = ../res/2.txt
+patch[1] = 12
+patch[2] = 2
+memory[0] = 5534943
+stop = halt
+
+[day 5, air conditioner]
+program = ../res/5.txt
+input = 1
+output = 0, 0, 0, 0, 0, 0, 0, 0, 0, 15097178
+stop = halt
+
+[day 5, thermal radiators]
+program = ../res/5.txt
+input = 5
+output = 1558663
+
+[day 9, self-test]
+program = ../res/9.txt
+input = 1
+output = 3345854957
+stop = halt
+
+[day 21, walk]
+program = ../res/21.txt
+input = ascii "NOT A T\nOR T J\nNOT C T\nAND D T\nOR T J\n"
+input = ascii "WALK\n"
+last = 19355790
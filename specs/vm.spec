@@ -0,0 +1,22 @@
+# Small programs exercising the VM itself.
+
+[equal to 8]
+program = programs/eq8.int
+input = 8
+output = 1
+
+[not equal to 8]
+program = programs/eq8.int
+input = 7
+output = 0
+
+[waits for input]
+program = programs/eq8.int
+output =
+stop = input
+
+[faults on opcode 42]
+program = programs/fault.int
+input = 1
+output = 1
+stop = fault
@@ -0,0 +1,376 @@
+use super::{read_input, Input};
+use aoc19_rs::solution::{self, Entry};
+
+use std::collections::hash_map::{self, HashMap};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "usage: aoc19 bench [all|watched|<day>[:part]...] [--runs N] \
+                     [--json path] [--csv path] [--baseline path] [--threshold percent]";
+
+/// The parts whose timings we keep an eye on.
+const WATCHED: [(u32, u32); 3] = [(12, 2), (16, 2), (18, 1)];
+
+#[derive(Debug)]
+struct Options {
+    parts: Vec<(Entry, u32)>,
+    runs: usize,
+    json: Option<PathBuf>,
+    csv: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+    threshold: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Timing {
+    day: u32,
+    part: u32,
+    runs: usize,
+    min: u64,
+    median: u64,
+    max: u64,
+}
+
+fn parse_options<I>(args: I) -> Result<Options, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut opts = Options {
+        parts: vec![],
+        runs: 10,
+        json: None,
+        csv: None,
+        baseline: None,
+        threshold: 10.0,
+    };
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+
+        match arg.as_str() {
+            "--runs" => {
+                opts.runs = value()?
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or("--runs needs a positive number")?
+            }
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            "--json" => opts.json = Some(value()?.into()),
+            "--csv" => opts.csv = Some(value()?.into()),
+            "--baseline" => opts.baseline = Some(value()?.into()),
+            "--threshold" => {
+                opts.threshold = value()?
+                    .parse()
+                    .map_err(|_| "--threshold needs a percentage")?
+            }
+            "all" => {
+                for entry in solution::registry() {
+                    opts.parts.push((entry, 1));
+                    opts.parts.push((entry, 2));
+                }
+            }
+            "watched" => {
+                for &(day, part) in WATCHED.iter() {
+                    opts.parts.push((solution::lookup(day).unwrap(), part));
+                }
+            }
+            spec => {
+                let mut fields = spec.splitn(2, ':');
+                let entry = fields
+                    .next()
+                    .and_then(|day| day.parse().ok())
+                    .and_then(solution::lookup)
+                    .ok_or_else(|| format!("no solution for `{}`", spec))?;
+
+                match fields.next() {
+                    Some("1") => opts.parts.push((entry, 1)),
+                    Some("2") => opts.parts.push((entry, 2)),
+                    None => {
+                        opts.parts.push((entry, 1));
+                        opts.parts.push((entry, 2));
+                    }
+                    Some(_) => return Err(format!("invalid part in `{}`", spec)),
+                }
+            }
+        }
+    }
+
+    if opts.parts.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    Ok(opts)
+}
+
+fn nanos(d: Duration) -> u64 {
+    d.as_nanos() as u64
+}
+
+/// Times `runs` calls of one part, or returns `None` if the day doesn't solve it.
+fn measure(entry: &Entry, part: u32, input: &str, runs: usize) -> Result<Option<Timing>, String> {
+    let mut times = Vec::with_capacity(runs);
+
+    for _ in 0..runs {
+        let start = Instant::now();
+        let answer = if part == 1 {
+            entry.part_1(input)
+        } else {
+            match entry.part_2(input) {
+                Some(answer) => answer,
+                None => return Ok(None),
+            }
+        };
+        answer.map_err(|e| format!("day {}: {}", entry.day, e))?;
+        times.push(nanos(start.elapsed()));
+    }
+
+    times.sort_unstable();
+
+    Ok(Some(Timing {
+        day: entry.day,
+        part,
+        runs,
+        min: times[0],
+        median: times[runs / 2],
+        max: times[runs - 1],
+    }))
+}
+
+fn to_json(timings: &[Timing]) -> String {
+    let mut s = String::from("[\n");
+
+    for (i, t) in timings.iter().enumerate() {
+        writeln!(
+            s,
+            "  {{\"day\": {}, \"part\": {}, \"runs\": {}, \"min_ns\": {}, \"median_ns\": {}, \
+             \"max_ns\": {}}}{}",
+            t.day,
+            t.part,
+            t.runs,
+            t.min,
+            t.median,
+            t.max,
+            if i + 1 < timings.len() { "," } else { "" }
+        )
+        .unwrap();
+    }
+
+    s + "]\n"
+}
+
+fn to_csv(timings: &[Timing]) -> String {
+    let mut s = String::from("day,part,runs,min_ns,median_ns,max_ns\n");
+
+    for t in timings {
+        writeln!(
+            s,
+            "{},{},{},{},{},{}",
+            t.day, t.part, t.runs, t.min, t.median, t.max
+        )
+        .unwrap();
+    }
+
+    s
+}
+
+/// Reads timings written by `to_csv`, or by `to_json` when the text starts with `[`.
+fn parse_timings(text: &str) -> Result<Vec<Timing>, String> {
+    let records = if text.trim_start().starts_with('[') {
+        text.split('{')
+            .skip(1)
+            .map(|obj| {
+                obj.split('}')
+                    .next()
+                    .unwrap_or("")
+                    .split(',')
+                    .filter_map(|field| {
+                        let mut kv = field.splitn(2, ':');
+                        let key = kv.next()?.trim().trim_matches('"');
+                        Some((key.to_string(), kv.next()?.trim().to_string()))
+                    })
+                    .collect::<HashMap<_, _>>()
+            })
+            .collect::<Vec<_>>()
+    } else {
+        let mut lines = text.lines();
+        let header = lines
+            .next()
+            .ok_or("empty baseline")?
+            .split(',')
+            .collect::<Vec<_>>();
+
+        lines
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                header
+                    .iter()
+                    .map(|key| key.to_string())
+                    .zip(line.split(',').map(|v| v.trim().to_string()))
+                    .collect::<HashMap<_, _>>()
+            })
+            .collect()
+    };
+
+    records
+        .iter()
+        .map(|r| {
+            let field = |key: &str| {
+                r.get(key)
+                    .and_then(|v| v.parse::<u64>().ok())
+                    .ok_or_else(|| format!("missing or invalid `{}` in baseline", key))
+            };
+
+            Ok(Timing {
+                day: field("day")? as u32,
+                part: field("part")? as u32,
+                runs: field("runs")? as usize,
+                min: field("min_ns")?,
+                median: field("median_ns")?,
+                max: field("max_ns")?,
+            })
+        })
+        .collect()
+}
+
+/// Describes every part whose median got slower than the baseline's by more than
+/// `threshold` percent.
+fn regressions(timings: &[Timing], baseline: &[Timing], threshold: f64) -> Vec<String> {
+    timings
+        .iter()
+        .filter_map(|t| {
+            let base = baseline
+                .iter()
+                .find(|b| (b.day, b.part) == (t.day, t.part))?;
+            let change = (t.median as f64 / base.median.max(1) as f64 - 1.0) * 100.0;
+
+            if change > threshold {
+                Some(format!(
+                    "day {} part {}: {:?} -> {:?} (+{:.1}%)",
+                    t.day,
+                    t.part,
+                    Duration::from_nanos(base.median),
+                    Duration::from_nanos(t.median),
+                    change
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Runs the benchmarks and returns whether every part stayed within the threshold.
+pub fn main<I>(args: I) -> Result<bool, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let opts = parse_options(args)?;
+    let baseline = match &opts.baseline {
+        Some(path) => parse_timings(
+            &fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?,
+        )?,
+        None => vec![],
+    };
+
+    let mut inputs = HashMap::new();
+    let mut timings = vec![];
+
+    println!(
+        "{:>3} {:>4} {:>14} {:>14} {:>14}",
+        "day", "part", "min", "median", "max"
+    );
+
+    for (entry, part) in opts.parts.iter() {
+        if let hash_map::Entry::Vacant(e) = inputs.entry(entry.day) {
+            e.insert(read_input(entry.day, &Input::Default)?);
+        }
+
+        if let Some(t) = measure(entry, *part, &inputs[&entry.day], opts.runs)? {
+            println!(
+                "{:>3} {:>4} {:>14} {:>14} {:>14}",
+                t.day,
+                t.part,
+                format!("{:?}", Duration::from_nanos(t.min)),
+                format!("{:?}", Duration::from_nanos(t.median)),
+                format!("{:?}", Duration::from_nanos(t.max))
+            );
+            timings.push(t);
+        }
+    }
+
+    if let Some(path) = &opts.json {
+        write(path, &to_json(&timings))?;
+    }
+    if let Some(path) = &opts.csv {
+        write(path, &to_csv(&timings))?;
+    }
+
+    let slower = regressions(&timings, &baseline, opts.threshold);
+    for r in slower.iter() {
+        eprintln!("regression: {}", r);
+    }
+
+    Ok(slower.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(day: u32, part: u32, median: u64) -> Timing {
+        Timing {
+            day,
+            part,
+            runs: 3,
+            min: median - 1,
+            median,
+            max: median + 1,
+        }
+    }
+
+    #[test]
+    fn formats_round_trip() {
+        let timings = vec![timing(12, 2, 4_500_000), timing(18, 1, 330_000_000)];
+
+        assert_eq!(parse_timings(&to_json(&timings)), Ok(timings.clone()));
+        assert_eq!(parse_timings(&to_csv(&timings)), Ok(timings.clone()));
+        assert!(parse_timings("day,part\n1,2").is_err());
+    }
+
+    #[test]
+    fn flags_regressions() {
+        let baseline = vec![timing(12, 2, 1000), timing(16, 2, 1000)];
+        let timings = vec![timing(12, 2, 1200), timing(16, 2, 1050), timing(18, 1, 9)];
+
+        let slower = regressions(&timings, &baseline, 10.0);
+        assert_eq!(slower.len(), 1);
+        assert!(slower[0].starts_with("day 12 part 2"), "{}", slower[0]);
+    }
+
+    #[test]
+    fn options() {
+        let parse = |args: &[&str]| parse_options(args.iter().map(|s| s.to_string()));
+
+        let opts = parse(&["watched", "4:1", "--runs", "3"]).unwrap();
+        let parts = opts
+            .parts
+            .iter()
+            .map(|(e, p)| (e.day, *p))
+            .collect::<Vec<_>>();
+        assert_eq!(parts, vec![(12, 2), (16, 2), (18, 1), (4, 1)]);
+        assert_eq!(opts.runs, 3);
+
+        assert_eq!(parse(&["all"]).unwrap().parts.len(), 44);
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["4:3"]).is_err());
+        assert!(parse(&["4", "--runs", "0"]).is_err());
+    }
+}
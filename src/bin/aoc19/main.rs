@@ -0,0 +1,191 @@
+mod bench;
+mod spec;
+mod verify;
+
+use aoc19_rs::input;
+use aoc19_rs::solution::{self, Entry};
+
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::time::Instant;
+
+const USAGE: &str = "usage: aoc19 <day|all> [part] [--input <path>|-]\n       aoc19 bench [all|watched|<day>[:part]...] [options]\n       aoc19 verify [manifest]\n       aoc19 spec [dir]";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Input {
+    Default,
+    Path(PathBuf),
+    Stdin,
+}
+
+#[derive(Debug)]
+struct Args {
+    days: Vec<Entry>,
+    part: Option<u32>,
+    input: Input,
+}
+
+fn parse_args<I>(args: I) -> Result<Args, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut positional = vec![];
+    let mut input = Input::Default;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" | "-i" => {
+                input = match args.next().as_deref() {
+                    Some("-") => Input::Stdin,
+                    Some(path) => Input::Path(path.into()),
+                    None => return Err("--input needs a path or `-`".to_string()),
+                }
+            }
+            "-" => input = Input::Stdin,
+            _ => positional.push(arg),
+        }
+    }
+
+    let days = match positional.first().map(String::as_str) {
+        Some("all") => solution::registry(),
+        Some(day) => {
+            let entry = day
+                .parse()
+                .ok()
+                .and_then(solution::lookup)
+                .ok_or_else(|| format!("no solution for day `{}`", day))?;
+            vec![entry]
+        }
+        None => return Err(USAGE.to_string()),
+    };
+
+    let part = match positional.get(1).map(String::as_str) {
+        Some("1") => Some(1),
+        Some("2") => Some(2),
+        Some(part) => return Err(format!("invalid part `{}`", part)),
+        None => None,
+    };
+
+    if positional.len() > 2 {
+        return Err(USAGE.to_string());
+    }
+    if days.len() > 1 && input != Input::Default {
+        return Err("`all` reads every day from its default input".to_string());
+    }
+
+    Ok(Args { days, part, input })
+}
+
+fn read_input(day: u32, source: &Input) -> Result<String, String> {
+    match source {
+        Input::Default => input::load(day),
+        Input::Path(path) => input::read(path),
+        Input::Stdin => input::stdin(),
+    }
+    .map_err(|e| e.to_string())
+}
+
+fn run(entry: &Entry, part: Option<u32>, input: &str) -> Result<(), String> {
+    if part != Some(2) {
+        let start = Instant::now();
+        let answer = entry.part_1(input).map_err(|e| e.to_string())?;
+        println!(
+            "day {} part 1: {} ({:?})",
+            entry.day,
+            answer,
+            start.elapsed()
+        );
+    }
+
+    if part != Some(1) {
+        let start = Instant::now();
+        match entry.part_2(input) {
+            Some(answer) => {
+                let answer = answer.map_err(|e| e.to_string())?;
+                println!(
+                    "day {} part 2: {} ({:?})",
+                    entry.day,
+                    answer,
+                    start.elapsed()
+                )
+            }
+            None if part == Some(2) => return Err("no part 2".to_string()),
+            None => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let subcommand = match env::args().nth(1).as_deref() {
+        Some("bench") => Some(bench::main as fn(_) -> _),
+        Some("verify") => Some(verify::main as fn(_) -> _),
+        Some("spec") => Some(spec::main as fn(_) -> _),
+        _ => None,
+    };
+
+    if let Some(subcommand) = subcommand {
+        match subcommand(env::args().skip(2)) {
+            Ok(true) => return,
+            Ok(false) => process::exit(1),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(2);
+            }
+        }
+    }
+
+    let args = parse_args(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+
+    let mut failed = false;
+
+    for entry in args.days.iter() {
+        let result =
+            read_input(entry.day, &args.input).and_then(|input| run(entry, args.part, &input));
+
+        if let Err(e) = result {
+            eprintln!("day {}: {}", entry.day, e);
+            failed = true;
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn arguments() {
+        let args = parse(&["4", "2", "--input", "-"]).unwrap();
+        assert_eq!(args.days.len(), 1);
+        assert_eq!(args.days[0].day, 4);
+        assert_eq!((args.part, args.input), (Some(2), Input::Stdin));
+
+        let args = parse(&["all"]).unwrap();
+        assert_eq!(args.days.len(), 22);
+        assert_eq!((args.part, args.input), (None, Input::Default));
+
+        assert_eq!(
+            parse(&["7", "-i", "in.txt"]).unwrap().input,
+            Input::Path("in.txt".into())
+        );
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["23"]).is_err());
+        assert!(parse(&["1", "3"]).is_err());
+        assert!(parse(&["all", "-"]).is_err());
+    }
+}
@@ -0,0 +1,23 @@
+use aoc19_rs::intcode::spec;
+
+use std::path::Path;
+
+const DEFAULT_DIR: &str = "specs";
+
+/// Runs every spec file in a directory and returns whether all cases passed.
+pub fn main<I>(args: I) -> Result<bool, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let args = args.into_iter().collect::<Vec<_>>();
+    let dir = match args.as_slice() {
+        [] => DEFAULT_DIR,
+        [dir] => dir.as_str(),
+        _ => return Err("usage: aoc19 spec [dir]".to_string()),
+    };
+
+    let report = spec::run_dir(Path::new(dir)).map_err(|e| e.to_string())?;
+    println!("{}", report);
+
+    Ok(report.failed() == 0)
+}
@@ -0,0 +1,58 @@
+use aoc19_rs::manifest::{self, Status};
+
+use std::path::Path;
+
+const DEFAULT_MANIFEST: &str = "res/answers.txt";
+
+/// Checks every day against the manifest and returns whether they all passed.
+pub fn main<I>(args: I) -> Result<bool, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let args = args.into_iter().collect::<Vec<_>>();
+    let path = match args.as_slice() {
+        [] => DEFAULT_MANIFEST,
+        [path] => path.as_str(),
+        _ => return Err("usage: aoc19 verify [manifest]".to_string()),
+    };
+
+    let checks = manifest::verify(&manifest::load(Path::new(path)).map_err(|e| e.to_string())?);
+
+    println!(
+        "{:>3} {:>4}  {:<20} {:<18} status",
+        "day", "part", "input", "answer"
+    );
+
+    for c in checks.iter() {
+        let status = match &c.status {
+            Status::Pass => "ok".to_string(),
+            Status::Fail { expected } => format!("FAIL, expected {}", expected),
+            Status::Missing => "missing".to_string(),
+            Status::Error(e) => format!("ERROR, {}", e),
+        };
+
+        println!(
+            "{:>3} {:>4}  {:<20} {:<18} {}",
+            c.day,
+            c.part,
+            c.input
+                .as_ref()
+                .and_then(|p| p.file_name())
+                .map_or("-".into(), |name| name.to_string_lossy()),
+            c.answer.as_deref().unwrap_or("-"),
+            status
+        );
+    }
+
+    let count = |f: fn(&Status) -> bool| checks.iter().filter(|c| f(&c.status)).count();
+    let failed = count(|s| matches!(s, Status::Fail { .. } | Status::Error(_)));
+
+    println!(
+        "\n{} passed, {} failed, {} missing",
+        count(|s| *s == Status::Pass),
+        failed,
+        count(|s| *s == Status::Missing)
+    );
+
+    Ok(failed == 0)
+}
@@ -1,15 +1,16 @@
-use std::str::FromStr;
+use crate::parse::{self, ParseError};
+use crate::solution::{Error, Solution};
 
-pub fn part_1(s: &str) -> i32 {
-    s.lines()
-        .map(|s| fuel_for_mass(i32::from_str(s).unwrap()))
-        .sum()
+pub fn part_1(s: &str) -> Result<i32, ParseError> {
+    Ok(parse_masses(s)?.into_iter().map(fuel_for_mass).sum())
 }
 
-pub fn part_2(s: &str) -> i32 {
-    s.lines()
-        .map(|s| total_fuel(i32::from_str(s).unwrap()))
-        .sum()
+pub fn part_2(s: &str) -> Result<i32, ParseError> {
+    Ok(parse_masses(s)?.into_iter().map(total_fuel).sum())
+}
+
+fn parse_masses(s: &str) -> Result<Vec<i32>, ParseError> {
+    s.lines().map(|line| parse::number(s, line)).collect()
 }
 
 fn total_fuel(m: i32) -> i32 {
@@ -26,26 +27,42 @@ fn fuel_for_mass(m: i32) -> i32 {
     m / 3 - 2
 }
 
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = i32;
+    type Part2 = i32;
+
+    fn part_1(input: &str) -> Result<i32, Error> {
+        part_1(input).map_err(Error::from)
+    }
+
+    fn part_2(input: &str) -> Option<Result<i32, Error>> {
+        Some(part_2(input).map_err(Error::from))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1("12"), 2);
-        assert_eq!(part_1("14"), 2);
-        assert_eq!(part_1("1969"), 654);
-        assert_eq!(part_1("100756"), 33583);
+        assert_eq!(part_1("12"), Ok(2));
+        assert_eq!(part_1("14"), Ok(2));
+        assert_eq!(part_1("1969"), Ok(654));
+        assert_eq!(part_1("100756"), Ok(33583));
 
-        assert_eq!(part_1(include_str!("../res/1.txt")), 3_212_842);
+        assert_eq!(part_1(&input::load(1).unwrap()), Ok(3_212_842));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2("14"), 2);
-        assert_eq!(part_2("1969"), 966);
-        assert_eq!(part_2("100756"), 50346);
+        assert_eq!(part_2("14"), Ok(2));
+        assert_eq!(part_2("1969"), Ok(966));
+        assert_eq!(part_2("100756"), Ok(50346));
 
-        assert_eq!(part_2(include_str!("../res/1.txt")), 4_816_402);
+        assert_eq!(part_2(&input::load(1).unwrap()), Ok(4_816_402));
     }
 }
@@ -1,38 +1,35 @@
+use crate::grid::Grid;
+use crate::parse::ParseError;
+use crate::solution::{Error, Solution};
+
 use itertools::Itertools;
 use num::Integer;
 
-pub fn part_1(s: &str) -> usize {
-    let map = Map::new(s);
-
-    let mut max_asteroids_seen = std::usize::MIN;
-    let mut coords = (0, 0);
+pub fn part_1(s: &str) -> Result<usize, Error> {
+    let map = parse_map(s)?;
 
-    for y in 0..map.h {
-        for x in 0..map.w {
-            if map.at(x, y) {
-                let n = map.seen(x, y).len();
-                if n > max_asteroids_seen {
-                    max_asteroids_seen = n;
-                    coords = (x, y);
-                }
-            }
-        }
-    }
+    station(&map).map(|(_, n)| n).ok_or_else(no_asteroids)
+}
 
-    println!("Coordinates: {:?}", coords);
+pub fn part_2(s: &str) -> Result<usize, Error> {
+    let map = parse_map(s)?;
+    let ((x0, y0), n) = station(&map).ok_or_else(no_asteroids)?;
 
-    max_asteroids_seen
-}
+    // Only the first turn of the laser is simulated
+    if n < 200 {
+        return Err(Error::unsolvable(format!(
+            "the station sees {} asteroids, expected at least 200",
+            n
+        )));
+    }
 
-pub fn part_2(s: &str) -> usize {
-    let tgt = Map::new(s)
-        .seen(22, 28)
+    let tgt = seen(&map, (x0, y0))
         .into_iter()
-        .sorted_by_key(|&(x, y)| (angle(x as f64 - 22.0, 28.0 - y as f64) * 1e9) as i64)
+        .sorted_by_key(|&(x, y)| (angle(x as f64 - x0 as f64, y0 as f64 - y as f64) * 1e9) as i64)
         .nth(199)
         .unwrap();
 
-    tgt.0 * 100 + tgt.1
+    Ok(tgt.0 * 100 + tgt.1)
 }
 
 fn angle(x: f64, y: f64) -> f64 {
@@ -47,80 +44,116 @@ fn angle(x: f64, y: f64) -> f64 {
     }
 }
 
-#[derive(Debug)]
-struct Map {
-    data: Vec<bool>,
-    w: usize,
-    h: usize,
+fn no_asteroids() -> Error {
+    Error::unsolvable("no asteroids in the map")
 }
 
-impl Map {
-    fn new(s: &str) -> Map {
-        let w = s.lines().nth(0).unwrap().len();
-        let h = s.lines().count();
+fn parse_map(s: &str) -> Result<Grid<bool>, ParseError> {
+    Grid::parse(s, |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })
+}
 
-        let mut data = vec![false; w * h];
+/// The asteroid that sees the most others, and how many it sees.
+fn station(map: &Grid<bool>) -> Option<((usize, usize), usize)> {
+    let mut best = None;
 
-        for (y, line) in s.lines().enumerate() {
-            for (x, ch) in line.chars().enumerate() {
-                data[y * w + x] = ch == '#';
-            }
+    for (pos, _) in map.iter().filter(|&(_, &asteroid)| asteroid) {
+        let n = seen(map, pos).len();
+        if best.is_none_or(|(_, max)| n > max) {
+            best = Some((pos, n));
         }
-
-        Map { w, h, data }
     }
 
-    fn at(&self, x: usize, y: usize) -> bool {
-        self.data[y * self.w + x]
-    }
-
-    fn seen(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
-        let (x, y) = (x as isize, y as isize);
-        let mut seen = Vec::new();
+    best
+}
 
-        for j in 0..self.h as isize {
-            'x: for i in 0..self.w as isize {
-                if (i == x && j == y) || !self.at(i as usize, j as usize) {
-                    continue;
-                }
+fn seen(map: &Grid<bool>, (x, y): (usize, usize)) -> Vec<(usize, usize)> {
+    let (x, y) = (x as isize, y as isize);
+    let mut seen = Vec::new();
 
-                let (dx, dy) = (i - x, j - y);
+    'asteroid: for ((i, j), _) in map.iter().filter(|&(_, &asteroid)| asteroid) {
+        let (i, j) = (i as isize, j as isize);
+        if i == x && j == y {
+            continue;
+        }
 
-                let gcd = dx.gcd(&dy);
-                if gcd == 1 {
-                    seen.push((i as usize, j as usize));
-                    continue;
-                }
+        let (dx, dy) = (i - x, j - y);
 
-                let (sx, sy) = (dx / gcd, dy / gcd);
-                let (mut a, mut b) = (x + sx, y + sy);
+        let gcd = dx.gcd(&dy);
+        if gcd == 1 {
+            seen.push((i as usize, j as usize));
+            continue;
+        }
 
-                while (a, b) != (i, j) {
-                    if self.at(a as usize, b as usize) {
-                        continue 'x;
-                    }
+        let (sx, sy) = (dx / gcd, dy / gcd);
+        let (mut a, mut b) = (x + sx, y + sy);
 
-                    a += sx;
-                    b += sy;
-                }
-                seen.push((i as usize, j as usize));
+        while (a, b) != (i, j) {
+            if map[(a as usize, b as usize)] {
+                continue 'asteroid;
             }
+
+            a += sx;
+            b += sy;
         }
-        seen
+        seen.push((i as usize, j as usize));
+    }
+    seen
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn part_1(input: &str) -> Result<usize, Error> {
+        part_1(input)
+    }
+
+    fn part_2(input: &str) -> Option<Result<usize, Error>> {
+        Some(part_2(input))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/10.txt")), 326);
+        assert_eq!(part_1(&input::load(10).unwrap()), Ok(326));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/10.txt")), 1623);
+        assert_eq!(part_2(&input::load(10).unwrap()), Ok(1623));
+
+        // The same map moved one step right and down, so the station isn't where it was
+        let map = input::load(10).unwrap();
+        let width = map.lines().next().unwrap().len();
+        let shifted = std::iter::once(".".repeat(width + 1))
+            .chain(map.lines().map(|line| format!(".{}", line)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(part_2(&shifted), Ok(1724));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(
+            part_1("").unwrap_err().to_string(),
+            "no solution: no asteroids in the map"
+        );
+        assert_eq!(part_1(".\n."), Err(no_asteroids()));
+        assert_eq!(part_2("."), Err(no_asteroids()));
+        assert_eq!(
+            part_2("#").unwrap_err().to_string(),
+            "no solution: the station sees 0 asteroids, expected at least 200"
+        );
     }
 }
@@ -1,4 +1,7 @@
+use crate::grid::SparseGrid;
 use crate::intcode::*;
+use crate::ocr;
+use crate::solution::{Error, Solution};
 
 use std::collections::HashMap;
 
@@ -11,37 +14,22 @@ enum Direction {
 
 const DIRS: [Direction; 4] = [Direction::N, Direction::E, Direction::S, Direction::W];
 
-pub fn part_1(s: &str) -> usize {
-    paint(s, false).len()
+pub fn part_1(s: &str) -> Result<usize, Error> {
+    Ok(paint(s, false)?.len())
 }
 
-pub fn part_2(s: &str) -> &str {
-    let hull = paint(s, true);
-
-    let (x_min, y_min, x_max, y_max) = hull.keys().fold(
-        (std::i32::MAX, std::i32::MAX, std::i32::MIN, std::i32::MIN),
-        |(x_min, y_min, x_max, y_max), &(x, y)| {
-            (x_min.min(x), y_min.min(y), x_max.max(x), y_max.max(y))
-        },
-    );
-
-    for y in y_min..=y_max {
-        for x in x_min..=x_max {
-            if *hull.get(&(x, y)).unwrap_or(&false) {
-                print!("*");
-            } else {
-                print!(" ");
-            }
-        }
-        println!();
+pub fn part_2(s: &str) -> Result<String, Error> {
+    let mut hull = SparseGrid::new(false);
+
+    for (&(x, y), &white) in paint(s, true)?.iter() {
+        hull.set((x.into(), y.into()), white);
     }
 
-    // See day 8 :)
-    "BCKFPCRA"
+    ocr::read(hull.grid()).map_err(|e| Error::unsolvable(e.to_string()))
 }
 
-fn paint(program: &str, start_panel: bool) -> HashMap<(i32, i32), bool> {
-    let mut ic = Intcode::new(program);
+fn paint(program: &str, start_panel: bool) -> Result<HashMap<(i32, i32), bool>, Error> {
+    let mut ic = Intcode::try_new(program)?;
     let mut hull = HashMap::new();
 
     let (mut x, mut y) = (0, 0);
@@ -53,10 +41,14 @@ fn paint(program: &str, start_panel: bool) -> HashMap<(i32, i32), bool> {
         ic.push_input(*hull.get(&(x, y)).unwrap_or(&false) as Word);
 
         let (outs, stop_condition) = ic.run();
+        let (colour, turn) = match outs[..] {
+            [colour, turn, ..] => (colour, turn),
+            _ => return Err(missing_output()),
+        };
 
-        hull.insert((x, y), outs[0] != 0);
+        hull.insert((x, y), colour != 0);
 
-        dir = (dir + 3 - ((outs[1] as i32) << 1)) & 0x3;
+        dir = (dir + 3 - ((turn as i32) << 1)) & 0x3;
 
         match DIRS[dir as usize] {
             Direction::N => y -= 1,
@@ -70,20 +62,39 @@ fn paint(program: &str, start_panel: bool) -> HashMap<(i32, i32), bool> {
         }
     }
 
-    hull
+    Ok(hull)
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = usize;
+    type Part2 = String;
+
+    fn part_1(input: &str) -> Result<usize, Error> {
+        part_1(input)
+    }
+
+    fn part_2(input: &str) -> Option<Result<String, Error>> {
+        Some(part_2(input))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/11.txt")), 2255);
+        assert_eq!(part_1(&input::load(11).unwrap()), Ok(2255));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/11.txt")), "BCKFPCRA");
+        assert_eq!(
+            part_2(&input::load(11).unwrap()),
+            Ok("BCKFPCRA".to_string())
+        );
     }
 }
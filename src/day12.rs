@@ -1,3 +1,6 @@
+use crate::parse::{self, ParseError};
+use crate::solution::{Error, Solution};
+
 use num::Integer;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -10,13 +13,13 @@ pub fn part_1(mut ps: [Point3; 4]) -> i64 {
         // Update velocities
         for i in 0..4 {
             for j in i + 1..4 {
-                vs[i].0 += (ps[j].0 - ps[i].0).min(1).max(-1);
-                vs[i].1 += (ps[j].1 - ps[i].1).min(1).max(-1);
-                vs[i].2 += (ps[j].2 - ps[i].2).min(1).max(-1);
+                vs[i].0 += (ps[j].0 - ps[i].0).clamp(-1, 1);
+                vs[i].1 += (ps[j].1 - ps[i].1).clamp(-1, 1);
+                vs[i].2 += (ps[j].2 - ps[i].2).clamp(-1, 1);
 
-                vs[j].0 += (ps[i].0 - ps[j].0).min(1).max(-1);
-                vs[j].1 += (ps[i].1 - ps[j].1).min(1).max(-1);
-                vs[j].2 += (ps[i].2 - ps[j].2).min(1).max(-1);
+                vs[j].0 += (ps[i].0 - ps[j].0).clamp(-1, 1);
+                vs[j].1 += (ps[i].1 - ps[j].1).clamp(-1, 1);
+                vs[j].2 += (ps[i].2 - ps[j].2).clamp(-1, 1);
             }
         }
 
@@ -44,14 +47,58 @@ pub fn part_2(ps: [Point3; 4]) -> usize {
     x.lcm(&y).lcm(&z)
 }
 
+fn parse_moons(s: &str) -> Result<[Point3; 4], ParseError> {
+    let mut moons = [Point3(0, 0, 0); 4];
+    let mut lines = s.lines();
+
+    for moon in moons.iter_mut() {
+        let line = lines
+            .next()
+            .ok_or_else(|| ParseError::at_end(s, "expected 4 moons"))?;
+        let malformed = || {
+            ParseError::at(
+                s,
+                line,
+                format!("expected `<x=X, y=Y, z=Z>`, found `{}`", line),
+            )
+        };
+
+        let mut fields = line
+            .strip_prefix('<')
+            .and_then(|l| l.strip_suffix('>'))
+            .ok_or_else(malformed)?
+            .split(", ");
+        let mut coords = [0; 3];
+
+        for (c, axis) in coords.iter_mut().zip(["x=", "y=", "z="].iter()) {
+            let value = fields
+                .next()
+                .and_then(|f| f.strip_prefix(axis))
+                .ok_or_else(malformed)?;
+            *c = parse::number(s, value)?;
+        }
+        if fields.next().is_some() {
+            return Err(malformed());
+        }
+
+        *moon = Point3(coords[0], coords[1], coords[2]);
+    }
+
+    if let Some(line) = lines.next() {
+        return Err(ParseError::at(s, line, "expected 4 moons"));
+    }
+
+    Ok(moons)
+}
+
 fn find_loop_iteration(mut ps: [i64; 4]) -> usize {
     let mut vs = [0; 4];
 
     for i in 1.. {
         for i in 0..4 {
             for j in i + 1..4 {
-                vs[i] += (ps[j] - ps[i]).min(1).max(-1);
-                vs[j] += (ps[i] - ps[j]).min(1).max(-1);
+                vs[i] += (ps[j] - ps[i]).clamp(-1, 1);
+                vs[j] += (ps[i] - ps[j]).clamp(-1, 1);
             }
         }
 
@@ -69,6 +116,21 @@ fn find_loop_iteration(mut ps: [i64; 4]) -> usize {
     panic!("state does not repeat");
 }
 
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = i64;
+    type Part2 = usize;
+
+    fn part_1(input: &str) -> Result<i64, Error> {
+        parse_moons(input).map(part_1).map_err(Error::from)
+    }
+
+    fn part_2(input: &str) -> Option<Result<usize, Error>> {
+        Some(parse_moons(input).map(part_2).map_err(Error::from))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
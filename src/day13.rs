@@ -1,93 +1,60 @@
+use crate::intcode::framebuffer::Framebuffer;
 use crate::intcode::*;
+use crate::parse::ParseError;
+use crate::solution::{Error, Solution};
 
-use std::convert::TryFrom;
+const EMPTY: Word = 0;
+const BLOCK: Word = 2;
+const PADDLE: Word = 3;
+const BALL: Word = 4;
 
-#[derive(Debug, Clone, PartialEq)]
-enum Tile {
-    Empty,
-    Wall,
-    Block,
-    Paddle,
-    Ball,
-}
-
-impl TryFrom<Word> for Tile {
-    type Error = &'static str;
-
-    fn try_from(v: Word) -> Result<Self, Self::Error> {
-        match v {
-            0 => Ok(Tile::Empty),
-            1 => Ok(Tile::Wall),
-            2 => Ok(Tile::Block),
-            3 => Ok(Tile::Paddle),
-            4 => Ok(Tile::Ball),
-            _ => Err("invalid tile code"),
-        }
-    }
-}
-
-type Point = (i64, i64);
+type Point = (Word, Word);
 
 #[derive(Debug)]
 struct Game {
     code: Intcode,
-    grid: Vec<Tile>,
-    bounds: Point,
+    screen: Framebuffer,
     paddle: Point,
     ball: Point,
-    score: Word,
 }
 
 impl Game {
-    fn create(program: &str) -> Game {
+    fn create(program: &str) -> Result<Game, ParseError> {
         let mut game = Game {
-            code: Intcode::new(program).update(0, 2),
-            grid: vec![],
-            bounds: (0, 0),
+            code: Intcode::try_new(program)?.update(0, 2),
+            screen: Framebuffer::new().background(EMPTY).special(-1, 0),
             paddle: (0, 0),
             ball: (0, 0),
-            score: 0,
         };
 
         game.update();
-        game
+        Ok(game)
     }
 
     fn play(&mut self) {
         self.code.push_input(0);
 
         while self.update() {
-            self.code
-                .push_input((self.ball.0 - self.paddle.0).signum() as Word);
+            self.code.push_input((self.ball.0 - self.paddle.0).signum());
         }
     }
 
+    fn score(&self) -> Word {
+        self.screen.register(-1, 0).unwrap_or(0)
+    }
+
     fn update(&mut self) -> bool {
         let (outs, sc) = self.code.run();
 
-        // First iteration -> compute grid size and allocate grid
-        if self.bounds == (0, 0) {
-            self.bounds = outs.chunks_exact(3).fold((0, 0), |(w, h), out| {
-                (((out[0] + 1) as i64).max(w), ((out[1] + 1) as i64).max(h))
-            });
-            self.grid = vec![Tile::Empty; (self.bounds.0 * self.bounds.1) as usize];
-        }
-
-        for out in outs.chunks_exact(3) {
-            let (x, y, tile) = (out[0] as i64, out[1] as i64, out[2]);
-
-            if x == -1 {
-                self.score = tile;
-            } else {
-                let idx = (y * self.bounds.0 + x) as usize;
+        self.screen.write(&outs);
 
-                self.grid[idx] = Tile::try_from(tile).unwrap();
+        for (x, y) in self.screen.take_changes() {
+            let tile = self.screen.get(x, y);
 
-                if self.grid[idx] == Tile::Ball {
-                    self.ball = (x, y);
-                } else if self.grid[idx] == Tile::Paddle {
-                    self.paddle = (x, y);
-                }
+            if tile == BALL {
+                self.ball = (x, y);
+            } else if tile == PADDLE {
+                self.paddle = (x, y);
             }
         }
 
@@ -95,31 +62,43 @@ impl Game {
     }
 }
 
-pub fn part_1(s: &str) -> usize {
-    Game::create(s)
-        .grid
-        .iter()
-        .filter(|&tile| *tile == Tile::Block)
-        .count()
+pub fn part_1(s: &str) -> Result<usize, ParseError> {
+    Ok(Game::create(s)?.screen.count(BLOCK))
 }
 
-pub fn part_2(s: &str) -> Word {
-    let mut game = Game::create(s);
+pub fn part_2(s: &str) -> Result<Word, ParseError> {
+    let mut game = Game::create(s)?;
     game.play();
-    game.score
+    Ok(game.score())
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = usize;
+    type Part2 = Word;
+
+    fn part_1(input: &str) -> Result<usize, Error> {
+        part_1(input).map_err(Error::from)
+    }
+
+    fn part_2(input: &str) -> Option<Result<Word, Error>> {
+        Some(part_2(input).map_err(Error::from))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/13.txt")), 213);
+        assert_eq!(part_1(&input::load(13).unwrap()), Ok(213));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/13.txt")), 11441);
+        assert_eq!(part_2(&input::load(13).unwrap()), Ok(11441));
     }
 }
@@ -1,20 +1,23 @@
+use crate::parse::{self, ParseError};
+use crate::solution::{Error, Solution};
+
 use std::collections::HashMap;
-use std::str::FromStr;
 
 type Reactions<'a> = HashMap<&'a str, (u128, Vec<(&'a str, u128)>)>;
 
-pub fn part_1(s: &str) -> u128 {
-    let reactions = parse_reactions(s);
+pub fn part_1(s: &str) -> Result<u128, Error> {
+    let reactions = fuel_reactions(s)?;
     let mut chems = vec![("FUEL", 1)];
 
     reduce(&reactions, &mut chems);
-    ore_per_raw_mats(&reactions, &chems)
+    Ok(ore_per_raw_mats(&reactions, &chems))
 }
 
-pub fn part_2(s: &str) -> u128 {
-    let reactions = parse_reactions(s);
+pub fn part_2(s: &str) -> Result<u128, Error> {
+    let reactions = fuel_reactions(s)?;
 
-    let (mut l, mut r) = (1, 100_000_000);
+    // Every fuel takes at least one ore
+    let (mut l, mut r) = (0, 1_000_000_000_001);
     let mut ore;
 
     while r - l > 1 {
@@ -24,13 +27,13 @@ pub fn part_2(s: &str) -> u128 {
 
         ore = ore_per_raw_mats(&reactions, &chems);
 
-        if ore < 1_000_000_000_000 {
+        if ore <= 1_000_000_000_000 {
             l = (l + r) / 2;
         } else {
             r = (l + r) / 2;
         }
     }
-    l
+    Ok(l)
 }
 
 fn reduce<'a>(
@@ -59,7 +62,7 @@ fn reduce<'a>(
             continue;
         }
 
-        let multiplier = (required_amt + produced_amt - 1) / produced_amt;
+        let multiplier = required_amt.div_ceil(*produced_amt);
         let produced_amt = produced_amt * multiplier;
 
         if produced_amt > required_amt {
@@ -82,7 +85,7 @@ fn reduce<'a>(
 
     for (e, qty) in chemicals {
         let (produced, _) = &reactions[*e];
-        let multiplier = (*qty + produced - 1) / produced;
+        let multiplier = (*qty).div_ceil(*produced);
         let produced = multiplier * produced;
 
         if produced != *qty {
@@ -100,49 +103,165 @@ fn ore_per_raw_mats(reactions: &Reactions, mats: &[(&str, u128)]) -> u128 {
     mats.iter()
         .map(|(e, qty)| {
             let (produced, ore) = &reactions[*e];
-            ((qty + produced - 1) / produced) * ore[0].1
+            qty.div_ceil(*produced) * ore[0].1
         })
         .sum()
 }
 
-fn parse_reactions(s: &str) -> Reactions {
+fn parse_reactions(s: &str) -> Result<Reactions<'_>, ParseError> {
     let mut reactions = HashMap::new();
+    let mut ingredients = Vec::new();
 
     for line in s.lines() {
-        let mut line = line.split(" => ");
-        let (lhs, rhs) = (line.next().unwrap(), line.next().unwrap());
+        let mut sides = line.splitn(2, " => ");
+        let (lhs, rhs) = match (sides.next(), sides.next()) {
+            (Some(lhs), Some(rhs)) => (lhs, rhs),
+            _ => {
+                return Err(ParseError::at(
+                    s,
+                    line,
+                    format!("expected `<inputs> => <output>`, found `{}`", line),
+                ))
+            }
+        };
 
-        let (product, qty) = parse_element(rhs);
+        let (product, qty) = parse_element(s, rhs)?;
+        let inputs = lhs
+            .split(", ")
+            .map(|e| parse_element(s, e))
+            .collect::<Result<Vec<_>, _>>()?;
 
-        reactions.insert(
-            product,
-            (qty, lhs.split(", ").map(parse_element).collect::<Vec<_>>()),
-        );
+        ingredients.extend(inputs.iter().map(|&(elem, _)| elem));
+        reactions.insert(product, (qty, inputs));
+    }
+
+    if let Some(elem) = ingredients
+        .into_iter()
+        .find(|&e| e != "ORE" && !reactions.contains_key(e))
+    {
+        return Err(ParseError::at(
+            s,
+            elem,
+            format!("no reaction produces `{}`", elem),
+        ));
+    }
+
+    Ok(reactions)
+}
+
+/// Parses the reactions, checking that they produce `FUEL` without going round in circles.
+fn fuel_reactions(s: &str) -> Result<Reactions<'_>, Error> {
+    let reactions = parse_reactions(s)?;
+
+    if !reactions.contains_key("FUEL") {
+        return Err(Error::unsolvable("no reaction produces `FUEL`"));
+    }
+    if let Some(elem) = find_cycle(&reactions, "FUEL", &mut HashMap::new()) {
+        return Err(Error::unsolvable(format!(
+            "making `{}` takes `{}` itself",
+            elem, elem
+        )));
     }
 
-    reactions
+    Ok(reactions)
 }
 
-fn parse_element(s: &str) -> (&str, u128) {
-    let mut s = s.split_whitespace();
+/// Looks for a chemical that `elem` is made from which is also needed to make itself. `done`
+/// maps the chemicals visited so far to whether everything they're made from was checked.
+fn find_cycle<'a>(
+    reactions: &Reactions<'a>,
+    elem: &'a str,
+    done: &mut HashMap<&'a str, bool>,
+) -> Option<&'a str> {
+    match done.get(elem) {
+        Some(true) => return None,
+        Some(false) => return Some(elem),
+        None => {}
+    }
+    done.insert(elem, false);
 
-    let qty = u128::from_str(s.next().unwrap()).unwrap();
-    let elem = s.next().unwrap();
+    if let Some((_, inputs)) = reactions.get(elem) {
+        for &(input, _) in inputs {
+            if let Some(cycle) = find_cycle(reactions, input, done) {
+                return Some(cycle);
+            }
+        }
+    }
 
-    (elem, qty)
+    done.insert(elem, true);
+    None
+}
+
+fn parse_element<'a>(src: &str, s: &'a str) -> Result<(&'a str, u128), ParseError> {
+    let mut fields = s.split_whitespace();
+
+    match (fields.next(), fields.next(), fields.next()) {
+        (Some(qty), Some(elem), None) => Ok((elem, parse::number(src, qty)?)),
+        _ => Err(ParseError::at(
+            src,
+            s,
+            format!("expected `<quantity> <chemical>`, found `{}`", s),
+        )),
+    }
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = u128;
+    type Part2 = u128;
+
+    fn part_1(input: &str) -> Result<u128, Error> {
+        part_1(input)
+    }
+
+    fn part_2(input: &str) -> Option<Result<u128, Error>> {
+        Some(part_2(input))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/14.txt")), 114_125);
+        assert_eq!(part_1(&input::load(14).unwrap()), Ok(114_125));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/14.txt")), 12_039_407);
+        assert_eq!(part_2(&input::load(14).unwrap()), Ok(12_039_407));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(
+            part_1("10 ORE => 10 A\n7 A, 1 B => 1 FUEL")
+                .unwrap_err()
+                .to_string(),
+            "2:8: no reaction produces `B`"
+        );
+        assert_eq!(
+            part_1("10 ORE => 10 A\n7 A => FUEL")
+                .unwrap_err()
+                .to_string(),
+            "2:8: expected `<quantity> <chemical>`, found `FUEL`"
+        );
+        assert_eq!(
+            part_1("10 ORE => 10 A").unwrap_err().to_string(),
+            "no solution: no reaction produces `FUEL`"
+        );
+        assert_eq!(
+            part_1("1 A => 1 B\n1 B => 1 A\n1 A => 1 FUEL")
+                .unwrap_err()
+                .to_string(),
+            "no solution: making `A` takes `A` itself"
+        );
+        assert_eq!(
+            part_2("2 FUEL, 1 ORE => 1 FUEL").unwrap_err().to_string(),
+            "no solution: making `FUEL` takes `FUEL` itself"
+        );
     }
 }
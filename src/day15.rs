@@ -1,9 +1,12 @@
+use crate::grid::{Point, SparseGrid};
+use crate::intcode::explorer::{Explorer, Status};
 use crate::intcode::*;
+use crate::pathfinding;
+use crate::solution::{Error, Solution};
 
-use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
 
-type Maze = HashMap<(i64, i64), Tile>;
+type Maze = SparseGrid<Tile>;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Tile {
@@ -26,127 +29,104 @@ impl fmt::Display for Tile {
     }
 }
 
-pub fn part_1(s: &str) -> usize {
-    let (maze, tgt) = discover_maze(Intcode::new(s));
-
-    bfs(&maze, (0, 0))[&tgt]
+pub fn part_1(s: &str) -> Result<usize, Error> {
+    let (maze, tgt) = discover_maze(s)?;
+    let manhattan = |p: &Point| ((tgt.0 - p.0).abs() + (tgt.1 - p.1).abs()) as usize;
+
+    let paths = pathfinding::astar(
+        (0, 0),
+        |&p| open(&maze, p).map(|n| (n, 1)).collect::<Vec<_>>(),
+        manhattan,
+        |&p| p == tgt,
+    );
+    paths
+        .distance(&tgt)
+        .ok_or_else(|| Error::unsolvable("the oxygen system can't be reached"))
 }
 
-pub fn part_2(s: &str) -> usize {
-    let (maze, tgt) = discover_maze(Intcode::new(s));
+pub fn part_2(s: &str) -> Result<usize, Error> {
+    let (maze, tgt) = discover_maze(s)?;
+    let paths = pathfinding::bfs(tgt, |&p| open(&maze, p), |_| false);
 
-    *bfs(&maze, tgt).values().max().unwrap()
+    Ok(paths.distances().map(|(_, d)| d).max().unwrap_or(0))
 }
 
-fn discover_maze(mut ic: Intcode) -> (Maze, (i64, i64)) {
-    const DIRS: [Word; 4] = [1, 4, 2, 3];
-
-    let mut maze = HashMap::new();
-    let mut pos = (0, 0);
-    let mut tgt = (0, 0);
-    let mut dir = 0;
-
-    maze.insert(pos, Tile::Floor);
-
-    loop {
-        let try_dir = (dir + 1) % 4;
-
-        let new_pos = match DIRS[try_dir] {
-            1 => (pos.0, pos.1 - 1),
-            2 => (pos.0, pos.1 + 1),
-            3 => (pos.0 - 1, pos.1),
-            4 => (pos.0 + 1, pos.1),
-            _ => unreachable!(),
+fn discover_maze(s: &str) -> Result<(Maze, Point), Error> {
+    let world = Explorer::new([1, 2, 3, 4], |status| match status {
+        0 => Some(Status::Wall),
+        1 => Some(Status::Open),
+        2 => Some(Status::Special),
+        _ => None,
+    })
+    .explore(Intcode::try_new(s)?)
+    .map_err(|e| Error::unsolvable(e.to_string()))?;
+
+    // Everything beyond what the robot saw is walled off anyway
+    let mut maze = Maze::new(Tile::Wall);
+
+    for (&pos, status) in world.map.iter() {
+        let tile = match status {
+            Status::Wall => Tile::Wall,
+            Status::Open => Tile::Floor,
+            Status::Special => Tile::Oxygen,
         };
-
-        ic.push_input(DIRS[try_dir]);
-
-        match ic.run().0[0] {
-            0 => {
-                maze.insert(new_pos, Tile::Wall);
-                dir = (dir + 3) % 4;
-            }
-            1 => {
-                maze.insert(new_pos, Tile::Floor);
-                dir = try_dir;
-                pos = new_pos;
-            }
-            2 => {
-                maze.insert(new_pos, Tile::Oxygen);
-                dir = try_dir;
-                pos = new_pos;
-                tgt = new_pos;
-            }
-            _ => unreachable!(),
-        }
-
-        if new_pos == (0, 0) {
-            break;
-        }
+        maze.set(pos, tile);
     }
 
-    (maze, tgt)
-}
+    let &tgt = world
+        .specials
+        .first()
+        .ok_or_else(|| Error::unsolvable("the robot found no oxygen system"))?;
 
-fn bfs(maze: &Maze, start: (i64, i64)) -> HashMap<(i64, i64), usize> {
-    let mut distances = HashMap::with_capacity(maze.len());
-    let mut visited = HashSet::with_capacity(maze.len());
-    let mut q = VecDeque::with_capacity(maze.len());
-
-    distances.insert(start, 0);
-    visited.insert(start);
-    q.push_back(start);
-
-    while let Some(v) = q.pop_front() {
-        let steps = distances[&v];
-
-        for &w in [
-            (v.0 - 1, v.1),
-            (v.0 + 1, v.1),
-            (v.0, v.1 - 1),
-            (v.0, v.1 + 1),
-        ]
-        .iter()
-        {
-            if maze[&w] == Tile::Wall {
-                continue;
-            }
-            if visited.get(&w).is_none() {
-                distances.insert(w, steps + 1);
-                visited.insert(w);
-                q.push_back(w);
-            }
-        }
-    }
+    Ok((maze, tgt))
+}
 
-    distances
+fn open(maze: &Maze, p: Point) -> impl Iterator<Item = Point> + '_ {
+    maze.neighbours(p)
+        .filter(move |&n| *maze.get(n) != Tile::Wall)
 }
 
 #[allow(unused)]
 fn print(map: &Maze) {
-    for y in -24..24 {
-        for x in -24..24 {
-            if let Some(tile) = map.get(&(x, y)) {
-                print!("{}", tile);
-            } else {
-                print!(" ");
-            }
-        }
-        println!();
+    println!("{}", map);
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn part_1(input: &str) -> Result<usize, Error> {
+        part_1(input)
+    }
+
+    fn part_2(input: &str) -> Option<Result<usize, Error>> {
+        Some(part_2(input))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/15.txt")), 366);
+        assert_eq!(part_1(&input::load(15).unwrap()), Ok(366));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/15.txt")), 384);
+        assert_eq!(part_2(&input::load(15).unwrap()), Ok(384));
+    }
+
+    #[test]
+    fn rejects_unsolvable_input() {
+        // A robot that only ever hits walls
+        assert_eq!(
+            part_1("3,9,104,0,1105,1,0").unwrap_err().to_string(),
+            "no solution: the robot found no oxygen system"
+        );
     }
 }
@@ -1,8 +1,8 @@
-pub fn part_1(s: &str) -> String {
-    let mut phases = s
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as i64)
-        .collect::<Vec<_>>();
+use crate::parse::{self, ParseError};
+use crate::solution::{Error, Solution};
+
+pub fn part_1(s: &str) -> Result<String, ParseError> {
+    let mut phases = parse_signal(s)?;
 
     let base_pattern = [0, 1, 0, -1];
 
@@ -14,7 +14,7 @@ pub fn part_1(s: &str) -> String {
                     .zip(
                         base_pattern
                             .iter()
-                            .flat_map(|p| std::iter::repeat(p).take(i))
+                            .flat_map(|p| std::iter::repeat_n(p, i))
                             .cycle()
                             .skip(1),
                     )
@@ -26,19 +26,23 @@ pub fn part_1(s: &str) -> String {
             .collect::<Vec<_>>();
     }
 
-    phases[..8]
+    Ok(phases[..8]
         .iter()
         .map(|n| (*n as u8 + b'0') as char)
-        .collect()
+        .collect())
 }
 
-pub fn part_2(s: &str, off: usize) -> String {
-    let phases = s
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as i64)
-        .collect::<Vec<_>>();
+pub fn part_2(s: &str, off: usize) -> Result<String, Error> {
+    let phases = parse_signal(s)?;
 
     let n = phases.len();
+    // Past the middle of the signal each digit only depends on the ones after it
+    if off < n * 10_000 / 2 || off > n * 10_000 - 8 {
+        return Err(Error::unsolvable(format!(
+            "offset {} is outside the second half of the signal",
+            off
+        )));
+    }
     let k = n * 10_000 - off;
 
     let mut fft = vec![0; k];
@@ -53,20 +57,86 @@ pub fn part_2(s: &str, off: usize) -> String {
         }
     }
 
-    fft[..8].iter().map(|n| (*n as u8 + b'0') as char).collect()
+    Ok(fft[..8].iter().map(|n| (*n as u8 + b'0') as char).collect())
+}
+
+fn parse_signal(s: &str) -> Result<Vec<i64>, ParseError> {
+    let phases = s
+        .char_indices()
+        .map(|(i, c)| {
+            c.to_digit(10)
+                .map(i64::from)
+                .ok_or_else(|| ParseError::at(s, &s[i..], format!("invalid digit `{}`", c)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if phases.len() < 8 {
+        return Err(ParseError::at_end(s, "expected at least 8 digits"));
+    }
+
+    Ok(phases)
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = String;
+    type Part2 = String;
+
+    fn part_1(input: &str) -> Result<String, Error> {
+        part_1(input).map_err(Error::from)
+    }
+
+    fn part_2(input: &str) -> Option<Result<String, Error>> {
+        let off = input
+            .get(..7)
+            .ok_or_else(|| ParseError::at_end(input, "expected a 7-digit offset"))
+            .and_then(|off| parse::number(input, off));
+
+        Some(off.map_err(Error::from).and_then(|off| part_2(input, off)))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/16.txt")), "74608727");
+        assert_eq!(
+            part_1(&input::load(16).unwrap()),
+            Ok("74608727".to_string())
+        );
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/16.txt"), 5_973_847), "57920757");
+        assert_eq!(
+            part_2(&input::load(16).unwrap(), 5_973_847),
+            Ok("57920757".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(
+            part_1("12345").unwrap_err().to_string(),
+            "1:6: expected at least 8 digits"
+        );
+        assert_eq!(
+            Puzzle::part_2("9999999123")
+                .unwrap()
+                .unwrap_err()
+                .to_string(),
+            "no solution: offset 9999999 is outside the second half of the signal"
+        );
+        assert_eq!(
+            Puzzle::part_2("0000001123")
+                .unwrap()
+                .unwrap_err()
+                .to_string(),
+            "no solution: offset 1 is outside the second half of the signal"
+        );
     }
 }
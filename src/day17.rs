@@ -1,38 +1,20 @@
+use crate::grid::Grid;
 use crate::intcode::*;
+use crate::solution::{Error, Solution};
 
-pub fn part_1(s: &str) -> isize {
-    let (scaffolds, w, h) = find_scaffolding(s);
+pub fn part_1(s: &str) -> Result<usize, Error> {
+    let scaffolds = find_scaffolding(s)?;
 
-    let mut align_param = 0;
-
-    for y in 0..h {
-        'x: for x in 0..w {
-            if scaffolds[(y * w + x) as usize] != 35 {
-                continue;
-            }
-
-            for &pos in [
-                (x, y),
-                ((x + 1).min(w - 1), y),
-                ((x - 1).max(0), y),
-                (x, (y + 1).min(h - 1)),
-                (x, (y - 1).max(0)),
-            ]
-            .iter()
-            {
-                if scaffolds[(pos.1 * w + pos.0) as usize] != 35 {
-                    continue 'x;
-                }
-            }
-            align_param += x * y;
-        }
-    }
-    align_param
+    Ok(scaffolds
+        .iter()
+        .filter(|&(pos, &scaffold)| scaffold && scaffolds.neighbours(pos).all(|n| scaffolds[n]))
+        .map(|((x, y), _)| x * y)
+        .sum())
 }
 
-pub fn part_2(s: &str) -> Word {
+pub fn part_2(s: &str) -> Result<Word, Error> {
     // Computed by hand by simply following a straight path
-    let (outs, _) = Intcode::new(s)
+    let (outs, _) = Intcode::try_new(s)?
         .update(0, 2)
         .inputs(
             &(b"A,C,A,B,A,A,B,C,B,C\nL,12,L,8,R,12\nR,12,L,8,L,10\nL,10,L,8,L,12,R,12\nn\n"
@@ -42,30 +24,56 @@ pub fn part_2(s: &str) -> Word {
         )
         .run();
 
-    *outs.last().unwrap()
+    outs.last().copied().ok_or_else(missing_output)
+}
+
+fn find_scaffolding(s: &str) -> Result<Grid<bool>, Error> {
+    let (outs, _) = Intcode::try_new(s)?.run();
+    let view = outs.iter().map(|&c| c as u8 as char).collect::<String>();
+
+    // The robot itself isn't counted as scaffolding
+    Grid::parse(view.trim_end(), |c| Some(c == '#'))
+        .map_err(|e| Error::unsolvable(format!("the camera view isn't a grid: {}", e)))
 }
 
-fn find_scaffolding(s: &str) -> (Vec<Word>, isize, isize) {
-    let (outs, _) = Intcode::new(s).run();
+pub struct Puzzle;
 
-    let w = outs.iter().enumerate().find(|(_, &c)| c == 10).unwrap().0 as isize;
-    let scaffolds = outs.into_iter().filter(|c| *c != 10).collect::<Vec<_>>();
-    let h = scaffolds.len() as isize / w;
+impl Solution for Puzzle {
+    type Part1 = usize;
+    type Part2 = Word;
 
-    (scaffolds, w, h)
+    fn part_1(input: &str) -> Result<usize, Error> {
+        part_1(input)
+    }
+
+    fn part_2(input: &str) -> Option<Result<Word, Error>> {
+        Some(part_2(input))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/17.txt")), 10632);
+        assert_eq!(part_1(&input::load(17).unwrap()), Ok(10632));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/17.txt")), 1_356_191);
+        assert_eq!(part_2(&input::load(17).unwrap()), Ok(1_356_191));
+    }
+
+    #[test]
+    fn rejects_unsolvable_input() {
+        // A camera showing "#\n##"
+        assert_eq!(
+            part_1("104,35,104,10,104,35,104,35,99")
+                .unwrap_err()
+                .to_string(),
+            "no solution: the camera view isn't a grid: 2:1: expected 1 columns, found 2"
+        );
     }
 }
@@ -1,124 +1,113 @@
-use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use crate::grid::Grid;
+use crate::parse::ParseError;
+use crate::pathfinding;
+use crate::solution::{Error, Solution};
+
+use std::convert::Infallible;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Tile {
     Wall,
     Floor,
+    Entrance,
     Door(usize),
     Key(usize),
 }
 
 #[derive(Debug, Clone)]
 struct Maze {
-    map: HashMap<(usize, usize), Tile>,
+    map: Grid<Tile>,
     poi: Vec<(usize, usize)>,
 }
 
 impl Maze {
-    fn from(s: &str, keys: usize) -> Maze {
-        let keys = keys + 1;
-
-        let mut maze = Maze {
-            map: HashMap::new(),
-            poi: vec![(0, 0); keys],
-        };
-
-        for (y, line) in s.lines().enumerate() {
-            for (x, c) in line.bytes().enumerate() {
-                match c {
-                    b'#' => {
-                        maze.map.insert((x, y), Tile::Wall);
-                    }
-                    b'.' => {
-                        maze.map.insert((x, y), Tile::Floor);
-                    }
-                    b'a'..=b'z' => {
-                        maze.map.insert((x, y), Tile::Key((c - b'a' + 1) as usize));
-                        maze.poi[(c - b'a' + 1) as usize] = (x, y);
-                    }
-                    b'A'..=b'Z' => {
-                        maze.map.insert((x, y), Tile::Door((c - b'A' + 1) as usize));
-                    }
-                    b'@' => {
-                        maze.map.insert((x, y), Tile::Floor);
-                        maze.poi[0] = (x, y);
-                    }
-                    _ => unreachable!(),
+    fn from(s: &str, keys: usize) -> Result<Maze, ParseError> {
+        let map = Grid::parse(s, |c| match c {
+            '#' => Some(Tile::Wall),
+            '.' => Some(Tile::Floor),
+            '@' => Some(Tile::Entrance),
+            'a'..='z' => Some(Tile::Key(c as usize - 'a' as usize + 1)),
+            'A'..='Z' => Some(Tile::Door(c as usize - 'A' as usize + 1)),
+            _ => None,
+        })?;
+
+        let mut poi = vec![None; keys + 1];
+
+        for ((x, y), &tile) in map.iter() {
+            match tile {
+                Tile::Key(k) | Tile::Door(k) if k > keys => {
+                    let line = s.lines().nth(y).unwrap();
+                    return Err(ParseError::at(
+                        s,
+                        &line[x..],
+                        format!("`{}` is beyond the {} keys of the maze", &line[x..=x], keys),
+                    ));
                 }
+                Tile::Key(k) => poi[k] = Some((x, y)),
+                Tile::Entrance => poi[0] = Some((x, y)),
+                _ => (),
             }
         }
 
-        maze
+        if poi[0].is_none() {
+            return Err(ParseError::at_end(s, "no entrance `@` in the maze"));
+        }
+
+        Ok(Maze {
+            map,
+            poi: poi.into_iter().map(Option::unwrap_or_default).collect(),
+        })
     }
 }
 
 impl Maze {
     fn solve(&mut self) -> Option<isize> {
         let paths = self.compute_reachable_paths();
-
-        let mut visited = HashSet::new();
-        let mut q = BinaryHeap::new();
-
-        q.push((0, 0, 1));
-
-        while let Some((walked, pos, opened)) = q.pop() {
-            let walked = -walked;
-
-            if (opened & ((1 << self.poi.len()) - 1)) == ((1 << self.poi.len()) - 1) {
-                return Some(walked);
-            }
-
-            if !visited.insert((pos, opened)) {
-                continue;
-            }
-
-            for (key, (distance, required)) in paths[pos].iter().enumerate() {
-                if (opened & (1 << key)) != 0 || (opened & required) != *required {
-                    continue;
-                }
-                q.push((-(walked + distance), key, opened | (1 << key)));
-            }
-        }
-
-        None
+        let all = (1 << self.poi.len()) - 1;
+
+        let search = pathfinding::dijkstra(
+            (0, 1),
+            |&(pos, opened): &(usize, u32)| {
+                paths[pos]
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(key, path)| path.map(|path| (key, path)))
+                    .filter(move |&(key, (_, required))| {
+                        (opened & (1 << key)) == 0 && (opened & required) == required
+                    })
+                    .map(move |(key, (distance, _))| ((key, opened | (1 << key)), distance))
+            },
+            |&(_, opened)| opened == all,
+        );
+
+        let goal = search.goal()?;
+        search.distance(goal).map(|d| d as isize)
     }
 
-    fn compute_reachable_paths(&self) -> Vec<Vec<(isize, u32)>> {
-        let mut paths = vec![vec![(0, 0); self.poi.len()]; self.poi.len()];
+    /// The steps from each point of interest to every key it can reach, and the doors on
+    /// the way.
+    fn compute_reachable_paths(&self) -> Vec<Vec<Option<(usize, u32)>>> {
+        let mut paths = vec![vec![None; self.poi.len()]; self.poi.len()];
 
         for (poi, path) in paths.iter_mut().enumerate() {
-            let mut paths_to_keys = HashMap::with_capacity(self.poi.len());
-            let mut visited = HashSet::with_capacity(self.map.len());
-            let mut q = VecDeque::with_capacity(self.map.len());
-
-            paths_to_keys.insert(self.poi[poi], (0, 0));
-            visited.insert(self.poi[poi]);
-            q.push_back(self.poi[poi]);
-
-            while let Some(v) = q.pop_front() {
-                for &w in [
-                    (v.0 - 1, v.1),
-                    (v.0 + 1, v.1),
-                    (v.0, v.1 - 1),
-                    (v.0, v.1 + 1),
-                ]
-                .iter()
-                {
-                    if visited.get(&w).is_none() {
-                        let (mut steps, mut required) = paths_to_keys[&v];
-                        steps += 1;
-
-                        match self.map[&w] {
-                            Tile::Wall => continue,
-                            Tile::Door(d) => required |= 1 << d,
-                            Tile::Key(k) => path[k] = (steps, required),
-                            _ => (),
-                        };
-
-                        paths_to_keys.insert(w, (steps, required));
-                        visited.insert(w);
-                        q.push_back(w);
-                    }
+            let reached = pathfinding::bfs(
+                self.poi[poi],
+                |&v| {
+                    self.map
+                        .neighbours(v)
+                        .filter(move |&w| self.map[w] != Tile::Wall)
+                },
+                |_| false,
+            );
+
+            for (key, &pos) in self.poi.iter().enumerate().skip(1) {
+                if let Some(route) = reached.path(&pos) {
+                    // Every door along the way has to be opened first
+                    let required = route.iter().fold(0, |required, &w| match self.map[w] {
+                        Tile::Door(d) => required | 1 << d,
+                        _ => required,
+                    });
+                    path[key] = Some((route.len() - 1, required));
                 }
             }
         }
@@ -127,16 +116,59 @@ impl Maze {
     }
 }
 
-pub fn part_1(s: &str, keys: usize) -> isize {
-    Maze::from(s, keys).solve().unwrap()
+pub fn part_1(s: &str, keys: usize) -> Result<isize, Error> {
+    Maze::from(s, keys)?
+        .solve()
+        .ok_or_else(|| Error::unsolvable("the keys can't all be collected"))
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = isize;
+    type Part2 = Infallible;
+
+    fn part_1(input: &str) -> Result<isize, Error> {
+        let keys = input.chars().filter(char::is_ascii_lowercase).count();
+        part_1(input, keys)
+    }
+
+    fn part_2(_: &str) -> Option<Result<Infallible, Error>> {
+        None
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/18.txt"), 26), 4420);
+        assert_eq!(part_1(&input::load(18).unwrap(), 26), Ok(4420));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(
+            part_1("#####\n#a@?#\n#####", 1).unwrap_err().to_string(),
+            "2:4: invalid tile `?`"
+        );
+        assert_eq!(
+            part_1("#####\n#a@B#\n#####", 1).unwrap_err().to_string(),
+            "2:4: `B` is beyond the 1 keys of the maze"
+        );
+        assert_eq!(
+            part_1("#####\n#a..#\n#####", 1).unwrap_err().to_string(),
+            "3:6: no entrance `@` in the maze"
+        );
+        assert_eq!(
+            part_1("######\n#a@#b#\n######", 2).unwrap_err().to_string(),
+            "no solution: the keys can't all be collected"
+        );
+        assert_eq!(
+            part_1("#####\n#aA@#\n#####", 1).unwrap_err().to_string(),
+            "no solution: the keys can't all be collected"
+        );
     }
 }
@@ -1,27 +1,28 @@
 use crate::intcode::*;
+use crate::solution::{Error, Solution};
 
 use std::cmp::Ordering;
 use std::collections::HashMap;
 
 const N: Word = 100;
 
-pub fn part_1(s: &str) -> usize {
-    let ic = Intcode::new(s);
+pub fn part_1(s: &str) -> Result<usize, Error> {
+    let ic = Intcode::try_new(s)?;
     let mut beam_size = 0;
 
     for y in 0..50 {
         for x in 0..50 {
-            if beam_at(ic.clone(), (x, y)) {
+            if beam_at(ic.clone(), (x, y)).ok_or_else(missing_output)? {
                 beam_size += 1;
             }
         }
     }
 
-    beam_size
+    Ok(beam_size)
 }
 
-pub fn part_2(s: &str) -> Word {
-    let ic = Intcode::new(s);
+pub fn part_2(s: &str) -> Result<Word, Error> {
+    let ic = Intcode::try_new(s)?;
     let mut cache = HashMap::new();
 
     let (mut yl, mut yr) = (0, N * 20);
@@ -30,52 +31,78 @@ pub fn part_2(s: &str) -> Word {
     loop {
         let y = (yl + yr) / 2;
 
-        let (_, x0e) = *cache.entry(y).or_insert_with(|| find_ends(ic.clone(), y));
-        let (x1s, _) = *cache
-            .entry(y + n)
-            .or_insert_with(|| find_ends(ic.clone(), y + n));
+        let mut ends = |y| match cache.get(&y) {
+            Some(&ends) => Some(ends),
+            None => find_ends(ic.clone(), y).inspect(|&ends| {
+                cache.insert(y, ends);
+            }),
+        };
+        let ((_, x0e), (x1s, _)) = ends(y).zip(ends(y + n)).ok_or_else(missing_output)?;
 
         match x1s.cmp(&(x0e - n)) {
-            Ordering::Equal => return x1s * 10_000 + y,
+            Ordering::Equal => return Ok(x1s * 10_000 + y),
             Ordering::Greater => yl = y,
             Ordering::Less => yr = y,
         }
     }
 }
 
-fn find_ends(ic: Intcode, y: Word) -> (Word, Word) {
+/// The first and last `x` of the beam in row `y`, or `None` if the drone stops reporting.
+fn find_ends(ic: Intcode, y: Word) -> Option<(Word, Word)> {
     let (mut xs, mut xe) = (0, 0);
     for x in 0.. {
-        if beam_at(ic.clone(), (x, y)) {
+        if beam_at(ic.clone(), (x, y))? {
             xs = x;
             break;
         }
     }
     for x in (xs + 1).. {
-        if !beam_at(ic.clone(), (x, y)) {
+        if !beam_at(ic.clone(), (x, y))? {
             xe = x - 1;
             break;
         }
     }
-    (xs, xe)
+    Some((xs, xe))
 }
 
-fn beam_at(ic: Intcode, (x, y): (Word, Word)) -> bool {
+fn beam_at(ic: Intcode, (x, y): (Word, Word)) -> Option<bool> {
     let (outs, _) = ic.inputs(&[x, y]).run();
-    outs[0] == 1
+    outs.first().map(|&out| out == 1)
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = usize;
+    type Part2 = Word;
+
+    fn part_1(input: &str) -> Result<usize, Error> {
+        part_1(input)
+    }
+
+    fn part_2(input: &str) -> Option<Result<Word, Error>> {
+        Some(part_2(input))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/19.txt")), 126);
+        assert_eq!(part_1(&input::load(19).unwrap()), Ok(126));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/19.txt")), 11_351_625);
+        assert_eq!(part_2(&input::load(19).unwrap()), Ok(11_351_625));
+    }
+
+    #[test]
+    fn rejects_silent_programs() {
+        assert_eq!(part_1("99"), Err(missing_output()));
+        assert_eq!(part_2("99"), Err(missing_output()));
     }
 }
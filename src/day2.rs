@@ -1,40 +1,74 @@
-use crate::intcode::Intcode;
+use crate::intcode::symbolic;
+use crate::intcode::{Intcode, Word};
+use crate::parse::ParseError;
+use crate::solution::{Error, Solution};
 
-pub fn part_1(s: &str) -> i64 {
-    let mut ic = Intcode::new(s).update(1, 12).update(2, 2);
+pub fn part_1(s: &str) -> Result<i64, ParseError> {
+    let mut ic = Intcode::try_new(s)?.update(1, 12).update(2, 2);
     ic.run();
-    ic.value(0) as i64
+    Ok(ic.value(0) as i64)
 }
 
-pub fn part_2(s: &str) -> i64 {
-    let ic = Intcode::new(s);
+pub fn part_2(s: &str, target: Word) -> Result<Vec<(Word, Word)>, ParseError> {
+    let ic = Intcode::try_new(s)?;
 
-    for noun in 0..=99 {
-        for verb in 0..=99 {
-            let mut ic = ic.clone().update(1, noun).update(2, verb);
+    Ok(
+        symbolic::solve_memory(ic.memory(), &[(1, 0..=99), (2, 0..=99)], 0, target)
+            .into_iter()
+            .map(|vs| (vs[0], vs[1]))
+            .collect(),
+    )
+}
+
+pub struct Puzzle;
 
-            ic.run();
+impl Solution for Puzzle {
+    type Part1 = i64;
+    type Part2 = Word;
 
-            if ic.value(0) == 19_690_720 {
-                return (100 * noun + verb) as i64;
-            }
-        }
+    fn part_1(input: &str) -> Result<i64, Error> {
+        part_1(input).map_err(Error::from)
     }
 
-    panic!("noun/verb combination not found");
+    fn part_2(input: &str) -> Option<Result<Word, Error>> {
+        Some(
+            part_2(input, 19_690_720)
+                .map_err(Error::from)
+                .and_then(|pairs| match pairs.first() {
+                    Some(&(noun, verb)) => Ok(100 * noun + verb),
+                    None => Err(Error::unsolvable("no noun and verb produce 19690720")),
+                }),
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/2.txt")), 5_534_943);
+        assert_eq!(part_1(&input::load(2).unwrap()), Ok(5_534_943));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/2.txt")), 7_603);
+        assert_eq!(
+            part_2(&input::load(2).unwrap(), 19_690_720),
+            Ok(vec![(76, 3)])
+        );
+        assert_eq!(
+            part_2(&input::load(2).unwrap(), 5_534_943),
+            Ok(vec![(12, 2)])
+        );
+        assert_eq!(
+            Puzzle::part_2("1,0,0,0,99")
+                .unwrap()
+                .unwrap_err()
+                .to_string(),
+            "no solution: no noun and verb produce 19690720"
+        );
+        assert_eq!(part_2("1,0,0", 19_690_720), Ok(vec![]));
     }
 }
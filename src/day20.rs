@@ -1,209 +1,169 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use crate::grid::Grid;
+use crate::parse::ParseError;
+use crate::pathfinding;
+use crate::solution::{Error, Solution};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-enum Tile {
-    Wall,
-    Floor,
-    Teleport(char, char),
-}
+use itertools::Itertools;
+
+use std::collections::HashMap;
 
 type Point = (usize, usize);
-type Maze = HashMap<Point, Tile>;
-type Teleports = HashMap<(Tile, bool), Point>;
+/// Each floor next to a portal, with the floor on the other side and whether it's on the
+/// outer edge of the donut.
+type Portals = HashMap<Point, (Point, bool)>;
 
-pub fn part_1(s: &str) -> isize {
-    let (m, tps, start, end) = parse_maze(s);
+pub fn part_1(s: &str) -> Result<isize, Error> {
+    let (floors, portals, start, end) = parse_maze(s)?;
 
-    shortest_path(&m, &tps, start, end).unwrap() - 1
+    shortest_path(&floors, &portals, start, end).ok_or_else(unreachable_end)
 }
 
-pub fn part_2(s: &str) -> isize {
-    let (m, tps, start, end) = parse_maze(s);
+pub fn part_2(s: &str) -> Result<isize, Error> {
+    let (floors, portals, start, end) = parse_maze(s)?;
 
-    shortest_path_with_level(&m, &tps, start, end).unwrap() - 1
+    shortest_path_with_level(&floors, &portals, start, end).ok_or_else(unreachable_end)
 }
 
-fn shortest_path(m: &Maze, tps: &Teleports, start: Point, end: Point) -> Option<isize> {
-    let mut distances = HashMap::new();
-    let mut visited = HashSet::new();
-    let mut q = VecDeque::new();
-
-    distances.insert(start, 0);
-    visited.insert(start);
-    q.push_back(start);
-
-    while let Some(pos) = q.pop_front() {
-        let dist = distances[&pos];
-        let (x, y) = pos;
-
-        if pos == end {
-            return Some(dist);
-        }
-
-        for &w in [(x - 1, y), (x + 1, y), (x, y + 1), (x, y - 1)].iter() {
-            if m.get(&w).is_none() {
-                continue;
-            }
-            if visited.get(&w).is_none() {
-                visited.insert(w);
-
-                match m[&w] {
-                    Tile::Wall => (),
-                    Tile::Floor => {
-                        distances.insert(w, dist + 1);
-                        q.push_back(w);
-                    }
-                    tp @ Tile::Teleport('A', 'A') | tp @ Tile::Teleport('Z', 'Z') => {
-                        distances.insert(tps[&(tp, true)], dist);
-                        q.push_back(tps[&(tp, true)]);
-                    }
-                    tp @ Tile::Teleport(_, _) => {
-                        let next = if w == tps[&(tp, false)] {
-                            tps[&(tp, true)]
-                        } else {
-                            tps[&(tp, false)]
-                        };
-
-                        distances.insert(next, dist);
-                        q.push_back(next);
-                    }
-                }
-            }
-        }
-    }
-
-    None
+fn unreachable_end() -> Error {
+    Error::unsolvable("`ZZ` can't be reached from `AA`")
 }
 
-fn shortest_path_with_level(m: &Maze, tps: &Teleports, start: Point, end: Point) -> Option<isize> {
-    let mut distances = HashMap::new();
-    let mut visited = HashSet::new();
-    let mut q = VecDeque::new();
-
-    distances.insert((start, 0), 0);
-    visited.insert((start, 0));
-    q.push_back((start, 0));
+fn walk(floors: &Grid<bool>, pos: Point) -> impl Iterator<Item = Point> + '_ {
+    floors.neighbours(pos).filter(move |&w| floors[w])
+}
 
-    while let Some((pos, level)) = q.pop_front() {
-        let dist = distances[&(pos, level)];
-        let (x, y) = pos;
+fn shortest_path(
+    floors: &Grid<bool>,
+    portals: &Portals,
+    start: Point,
+    end: Point,
+) -> Option<isize> {
+    let paths = pathfinding::bfs(
+        start,
+        |&pos| walk(floors, pos).chain(portals.get(&pos).map(|&(to, _)| to)),
+        |&pos| pos == end,
+    );
+
+    paths.distance(&end).map(|d| d as isize)
+}
 
-        if pos == end {
-            return Some(dist);
-        }
+fn shortest_path_with_level(
+    floors: &Grid<bool>,
+    portals: &Portals,
+    start: Point,
+    end: Point,
+) -> Option<isize> {
+    let paths = pathfinding::bfs(
+        (start, 0),
+        |&(pos, level): &(Point, usize)| {
+            // Inner portals lead one level down, outer ones back up, and the outermost
+            // level has nothing above it
+            let jump = portals.get(&pos).and_then(|&(to, outer)| match outer {
+                false => Some((to, level + 1)),
+                true => level.checked_sub(1).map(|level| (to, level)),
+            });
+            walk(floors, pos).map(move |w| (w, level)).chain(jump)
+        },
+        |&state| state == (end, 0),
+    );
+
+    paths.distance(&(end, 0)).map(|d| d as isize)
+}
 
-        for &w in [(x - 1, y), (x + 1, y), (x, y + 1), (x, y - 1)].iter() {
-            if m.get(&w).is_none() {
-                continue;
-            }
-            if visited.get(&(w, level)).is_none() {
-                visited.insert((w, level));
-
-                match m[&w] {
-                    Tile::Floor => {
-                        distances.insert((w, level), dist + 1);
-                        q.push_back((w, level));
-                    }
-                    tp @ Tile::Teleport('A', 'A') | tp @ Tile::Teleport('Z', 'Z') => {
-                        if level == 0 {
-                            distances.insert((tps[&(tp, true)], 0), dist);
-                            q.push_back((tps[&(tp, true)], 0));
-                        }
-                    }
-                    tp @ Tile::Teleport(_, _) => {
-                        let (to, next_lvl) = if w == tps[&(tp, false)] {
-                            (tps[&(tp, true)], level + 1)
-                        } else if level != 0 {
-                            (tps[&(tp, false)], level - 1)
-                        } else {
-                            continue;
-                        };
-
-                        distances.insert((to, next_lvl), dist);
-                        q.push_back((to, next_lvl));
-                    }
-                    _ => (),
-                }
-            }
+fn parse_maze(s: &str) -> Result<(Grid<bool>, Portals, Point, Point), ParseError> {
+    let chars = Grid::parse_ragged(s, ' ', |c| match c {
+        '#' | '.' | ' ' | 'A'..='Z' => Some(c),
+        _ => None,
+    })?;
+    // Anything outside the grid reads as empty space
+    let at = |pos| chars.get(pos).copied().unwrap_or(' ');
+
+    let mut floors = Grid::new(chars.width(), chars.height(), false);
+    let mut labels = HashMap::<_, Vec<Point>>::new();
+
+    for (pos, _) in chars.iter().filter(|&(_, &c)| c == '.') {
+        floors[pos] = true;
+
+        if let Some(label) = chars.neighbours(pos).find(|&n| at(n).is_ascii_uppercase()) {
+            // Labels read left to right or top to bottom
+            let beyond = (
+                (2 * label.0).wrapping_sub(pos.0),
+                (2 * label.1).wrapping_sub(pos.1),
+            );
+            let name = if label > pos {
+                (at(label), at(beyond))
+            } else {
+                (at(beyond), at(label))
+            };
+            labels.entry(name).or_default().push(pos);
         }
     }
 
-    None
-}
-
-fn parse_maze(s: &str) -> (Maze, Teleports, Point, Point) {
-    let mut maze = Maze::new();
-
-    let grid = s
-        .lines()
-        .map(|line| line.chars().collect::<Vec<_>>())
+    let find = |name: char| {
+        labels
+            .get(&(name, name))
+            .map(|ends| ends[0])
+            .ok_or_else(|| ParseError::at_end(s, format!("no `{}{}` portal", name, name)))
+    };
+    let start = find('A')?;
+    let end = find('Z')?;
+
+    // The outer edge is the bounding box of the donut's walls and floors
+    let body = chars
+        .iter()
+        .filter(|&(_, &c)| c == '#' || c == '.')
+        .map(|(pos, _)| pos)
         .collect::<Vec<_>>();
-
-    for y in 0..grid.len() {
-        let line = &grid[y];
-
-        for x in 0..line.len() {
-            match line[x] {
-                '#' => {
-                    maze.insert((x, y), Tile::Wall);
-                }
-                '.' => {
-                    maze.insert((x, y), Tile::Floor);
-
-                    if line[x - 1].is_ascii_uppercase() {
-                        maze.insert((x - 1, y), Tile::Teleport(line[x - 2], line[x - 1]));
-                    } else if line[x + 1].is_ascii_uppercase() {
-                        maze.insert((x + 1, y), Tile::Teleport(line[x + 1], line[x + 2]));
-                    } else if grid[y - 1][x].is_ascii_uppercase() {
-                        maze.insert((x, y - 1), Tile::Teleport(grid[y - 2][x], grid[y - 1][x]));
-                    } else if grid[y + 1][x].is_ascii_uppercase() {
-                        maze.insert((x, y + 1), Tile::Teleport(grid[y + 1][x], grid[y + 2][x]));
-                    }
-                }
-                _ => (),
-            }
-        }
+    let (x0, x1) = body.iter().map(|p| p.0).minmax().into_option().unwrap();
+    let (y0, y1) = body.iter().map(|p| p.1).minmax().into_option().unwrap();
+    let outer = |(x, y): Point| x == x0 || x == x1 || y == y0 || y == y1;
+
+    let mut portals = Portals::new();
+    for ends in labels.values().filter(|ends| ends.len() == 2) {
+        portals.insert(ends[0], (ends[1], outer(ends[0])));
+        portals.insert(ends[1], (ends[0], outer(ends[1])));
     }
 
-    let w = maze.keys().max_by_key(|(x, _)| *x).unwrap().0;
-    let h = maze.keys().max_by_key(|(_, y)| *y).unwrap().1;
+    Ok((floors, portals, start, end))
+}
 
-    let tps = maze.iter().fold(Teleports::new(), |mut tps, (&pos, &t)| {
-        if let Tile::Teleport(_, _) = t {
-            tps.insert(
-                (t, pos.0 == 1 || pos.0 == w || pos.1 == 1 || pos.1 == h),
-                pos,
-            );
-        };
-        tps
-    });
+pub struct Puzzle;
 
-    let start = *maze
-        .iter()
-        .find(|&(_, t)| *t == Tile::Teleport('A', 'A'))
-        .unwrap()
-        .0;
+impl Solution for Puzzle {
+    type Part1 = isize;
+    type Part2 = isize;
 
-    let end = *maze
-        .iter()
-        .find(|&(_, t)| *t == Tile::Teleport('Z', 'Z'))
-        .unwrap()
-        .0;
+    fn part_1(input: &str) -> Result<isize, Error> {
+        part_1(input)
+    }
 
-    (maze, tps, start, end)
+    fn part_2(input: &str) -> Option<Result<isize, Error>> {
+        Some(part_2(input))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/20.txt")), 544);
+        assert_eq!(part_1(&input::load(20).unwrap()), Ok(544));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/20.txt")), 6238);
+        assert_eq!(part_2(&input::load(20).unwrap()), Ok(6238));
+    }
+
+    #[test]
+    fn rejects_unsolvable_input() {
+        let maze = "   A   Z\n   A   Z\n  #.###.#\n  #######";
+        assert_eq!(
+            part_1(maze).unwrap_err().to_string(),
+            "no solution: `ZZ` can't be reached from `AA`"
+        );
+        assert_eq!(part_2(maze), part_1(maze));
     }
 }
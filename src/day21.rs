@@ -1,7 +1,8 @@
 use crate::intcode::*;
+use crate::solution::{Error, Solution};
 
-pub fn part_1(s: &str) -> Word {
-    let mut ic = Intcode::new(s).inputs(
+pub fn part_1(s: &str) -> Result<Word, Error> {
+    let mut ic = Intcode::try_new(s)?.inputs(
         &[
             "NOT A T", "OR T J", "NOT C T", "AND D T", "OR T J", "WALK\n",
         ]
@@ -11,11 +12,11 @@ pub fn part_1(s: &str) -> Word {
         .collect::<Vec<_>>(),
     );
 
-    *ic.run().0.last().unwrap()
+    ic.run().0.last().copied().ok_or_else(missing_output)
 }
 
-pub fn part_2(s: &str) -> Word {
-    let mut ic = Intcode::new(s).inputs(
+pub fn part_2(s: &str) -> Result<Word, Error> {
+    let mut ic = Intcode::try_new(s)?.inputs(
         &[
             "NOT B T", "AND D T", "OR T J", "NOT A T", "AND D T", "OR T J", "NOT C T", "AND D T",
             "AND H T", "OR T J", "RUN\n",
@@ -26,20 +27,36 @@ pub fn part_2(s: &str) -> Word {
         .collect::<Vec<_>>(),
     );
 
-    *ic.run().0.last().unwrap()
+    ic.run().0.last().copied().ok_or_else(missing_output)
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = Word;
+    type Part2 = Word;
+
+    fn part_1(input: &str) -> Result<Word, Error> {
+        part_1(input)
+    }
+
+    fn part_2(input: &str) -> Option<Result<Word, Error>> {
+        Some(part_2(input))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/21.txt")), 19_355_790);
+        assert_eq!(part_1(&input::load(21).unwrap()), Ok(19_355_790));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/21.txt")), 1_140_920_822);
+        assert_eq!(part_2(&input::load(21).unwrap()), Ok(1_140_920_822));
     }
 }
@@ -1,4 +1,11 @@
-use std::str::FromStr;
+use crate::parse::{self, ParseError};
+use crate::solution::{Error, Solution};
+
+use num::Integer;
+
+use std::convert::Infallible;
+
+const DECK_SIZE: i32 = 10007;
 
 #[derive(Debug, Copy, Clone)]
 enum Shuffle {
@@ -7,9 +14,9 @@ enum Shuffle {
     Incrememnt(i32),
 }
 
-pub fn part_1(s: &str) -> usize {
-    let shuffles = parse_shuffles(s);
-    let mut deck = (0..=10006).collect::<Vec<_>>();
+pub fn part_1(s: &str) -> Result<usize, ParseError> {
+    let shuffles = parse_shuffles(s)?;
+    let mut deck = (0..DECK_SIZE).collect::<Vec<_>>();
 
     for shuffle in shuffles.into_iter() {
         match shuffle {
@@ -32,33 +39,106 @@ pub fn part_1(s: &str) -> usize {
         }
     }
 
-    deck.into_iter()
+    Ok(deck
+        .into_iter()
         .enumerate()
         .find(|(_, e)| *e == 2019)
         .unwrap()
-        .0
+        .0)
 }
 
-fn parse_shuffles(s: &str) -> Vec<Shuffle> {
+fn parse_shuffles(s: &str) -> Result<Vec<Shuffle>, ParseError> {
     s.lines()
         .map(|line| {
-            if &line[0..3] == "cut" {
-                Shuffle::Cut(i32::from_str(&line[4..]).unwrap())
-            } else if &line[0..9] == "deal into" {
-                Shuffle::NewStack
+            if line == "deal into new stack" {
+                Ok(Shuffle::NewStack)
+            } else if let Some(n) = line.strip_prefix("cut ") {
+                match parse::number::<i32>(s, n)? {
+                    cut if -DECK_SIZE < cut && cut < DECK_SIZE => Ok(Shuffle::Cut(cut)),
+                    _ => Err(ParseError::at(
+                        s,
+                        n,
+                        format!("can't cut {} cards from a deck of {}", n, DECK_SIZE),
+                    )),
+                }
+            } else if let Some(n) = line.strip_prefix("deal with increment ") {
+                match parse::number::<i32>(s, n)? {
+                    inc if inc > 0 && inc.gcd(&DECK_SIZE) == 1 => Ok(Shuffle::Incrememnt(inc)),
+                    _ => Err(ParseError::at(
+                        s,
+                        n,
+                        format!(
+                            "increment {} doesn't deal a deck of {} evenly",
+                            n, DECK_SIZE
+                        ),
+                    )),
+                }
             } else {
-                Shuffle::Incrememnt(i32::from_str(&line[20..]).unwrap())
+                Err(ParseError::at(
+                    s,
+                    line,
+                    format!("unknown shuffle `{}`", line),
+                ))
             }
         })
         .collect()
 }
 
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = usize;
+    type Part2 = Infallible;
+
+    fn part_1(input: &str) -> Result<usize, Error> {
+        part_1(input).map_err(Error::from)
+    }
+
+    fn part_2(_: &str) -> Option<Result<Infallible, Error>> {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/22.txt")), 6638);
+        assert_eq!(part_1(&input::load(22).unwrap()), Ok(6638));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(
+            part_1("cut 3\ndeal with increment x")
+                .unwrap_err()
+                .to_string(),
+            "2:21: invalid number `x`"
+        );
+        assert_eq!(
+            part_1("cut 3\nshuffle").unwrap_err().to_string(),
+            "2:1: unknown shuffle `shuffle`"
+        );
+        assert_eq!(
+            part_1("cut 20000").unwrap_err().to_string(),
+            "1:5: can't cut 20000 cards from a deck of 10007"
+        );
+        assert_eq!(
+            part_1("cut -20000").unwrap_err().to_string(),
+            "1:5: can't cut -20000 cards from a deck of 10007"
+        );
+        for inc in &["0", "-3", "10007", "20014"] {
+            assert_eq!(
+                part_1(&format!("deal with increment {}", inc))
+                    .unwrap_err()
+                    .to_string(),
+                format!(
+                    "1:21: increment {} doesn't deal a deck of 10007 evenly",
+                    inc
+                )
+            );
+        }
     }
 }
@@ -1,35 +1,36 @@
-use std::str::FromStr;
+use crate::parse::{self, ParseError};
+use crate::solution::{Error, Solution};
 
 type Pos = (i32, i32);
 
 #[derive(Debug)]
 struct Move(char, i32);
 
-pub fn part_1(s: &str) -> i32 {
-    let (fst, snd) = parse_paths(s);
+pub fn part_1(s: &str) -> Result<i32, Error> {
+    let (fst, snd) = parse_paths(s)?;
 
-    let mut min_dist = std::i32::MAX;
+    let mut min_dist = None;
 
     for i in 0..fst.len() - 1 {
         for j in 0..snd.len() - 1 {
             if let Some((x, y)) = intersect((&fst[i], &fst[i + 1]), (&snd[j], &snd[j + 1])) {
                 let dist = x.abs() + y.abs();
-                if dist > 0 && dist < min_dist {
-                    min_dist = dist;
+                if dist > 0 && min_dist.is_none_or(|min| dist < min) {
+                    min_dist = Some(dist);
                 }
             }
         }
     }
 
-    min_dist
+    min_dist.ok_or_else(no_crossing)
 }
 
-pub fn part_2(s: &str) -> i32 {
-    let (fst, snd) = parse_paths(s);
+pub fn part_2(s: &str) -> Result<i32, Error> {
+    let (fst, snd) = parse_paths(s)?;
 
     let mut fst_steps = 0;
     let mut snd_steps = 0;
-    let mut min_steps = std::i32::MAX;
+    let mut min_steps = None;
 
     for i in 0..fst.len() - 1 {
         let (p0, p1) = (&fst[i], &fst[i + 1]);
@@ -45,8 +46,8 @@ pub fn part_2(s: &str) -> i32 {
                     + (p2.0 - x).abs()
                     + (p2.1 - y).abs();
 
-                if p0.0 != 0 && p0.1 != 0 && steps < min_steps {
-                    min_steps = steps;
+                if (x, y) != (0, 0) && min_steps.is_none_or(|min| steps < min) {
+                    min_steps = Some(steps);
                 }
             }
 
@@ -57,15 +58,27 @@ pub fn part_2(s: &str) -> i32 {
         snd_steps = 0;
     }
 
-    min_steps
+    min_steps.ok_or_else(no_crossing)
 }
 
-fn parse_paths(s: &str) -> (Vec<Pos>, Vec<Pos>) {
-    let mut paths = s
-        .lines()
-        .map(|path| compute_path(path.split(',').map(parse_move)));
+fn no_crossing() -> Error {
+    Error::unsolvable("the wires never cross")
+}
 
-    (paths.next().unwrap(), paths.next().unwrap())
+fn parse_paths(s: &str) -> Result<(Vec<Pos>, Vec<Pos>), ParseError> {
+    let mut paths = s.lines().map(|path| {
+        path.split(',')
+            .map(|m| parse_move(s, m))
+            .collect::<Result<Vec<_>, _>>()
+            .map(compute_path)
+    });
+    let mut next = || {
+        paths
+            .next()
+            .unwrap_or_else(|| Err(ParseError::at_end(s, "expected two wires")))
+    };
+
+    Ok((next()?, next()?))
 }
 
 fn compute_path<I>(path: I) -> Vec<Pos>
@@ -80,7 +93,7 @@ where
             Move('D', n) => pos.1 -= n,
             Move('R', n) => pos.0 += n,
             Move('L', n) => pos.0 -= n,
-            _ => panic!("invalid move: {:?}", mv),
+            _ => unreachable!(),
         };
 
         acc.push(pos);
@@ -115,51 +128,93 @@ fn intersect(
     ret
 }
 
-fn parse_move(m: &str) -> Move {
-    let (dir, amt) = m.split_at(1);
+fn parse_move(s: &str, m: &str) -> Result<Move, ParseError> {
+    let dir = m
+        .chars()
+        .next()
+        .filter(|c| "UDRL".contains(*c))
+        .ok_or_else(|| ParseError::at(s, m, format!("invalid move `{}`", m)))?;
+
+    Ok(Move(dir, parse::number(s, &m[1..])?))
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = i32;
+    type Part2 = i32;
 
-    Move(dir.chars().nth(0).unwrap(), i32::from_str(amt).unwrap())
+    fn part_1(input: &str) -> Result<i32, Error> {
+        part_1(input)
+    }
+
+    fn part_2(input: &str) -> Option<Result<i32, Error>> {
+        Some(part_2(input))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1("R8,U5,L5,D3\nU7,R6,D4,L4"), 6);
+        assert_eq!(part_1("R8,U5,L5,D3\nU7,R6,D4,L4"), Ok(6));
 
         assert_eq!(
             part_1("R75,D30,R83,U83,L12,D49,R71,U7,L72\nU62,R66,U55,R34,D71,R55,D58,R83"),
-            159
+            Ok(159)
         );
 
         assert_eq!(
             part_1(
                 "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51\nU98,R91,D20,R16,D67,R40,U7,R15,U6,R7"
             ),
-            135
+            Ok(135)
         );
 
-        assert_eq!(part_1(include_str!("../res/3.txt")), 209);
+        assert_eq!(part_1(&input::load(3).unwrap()), Ok(209));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2("R8,U5,L5,D3\nU7,R6,D4,L4"), 30);
+        assert_eq!(part_2("R8,U5,L5,D3\nU7,R6,D4,L4"), Ok(30));
 
         assert_eq!(
             part_2("R75,D30,R83,U83,L12,D49,R71,U7,L72\nU62,R66,U55,R34,D71,R55,D58,R83"),
-            610
+            Ok(610)
         );
 
         assert_eq!(
             part_2(
                 "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51\nU98,R91,D20,R16,D67,R40,U7,R15,U6,R7"
             ),
-            410
+            Ok(410)
+        );
+
+        assert_eq!(part_2(&input::load(3).unwrap()), Ok(43_258));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(
+            part_1("R8,U5\nU7,X6").unwrap_err().to_string(),
+            "2:4: invalid move `X6`"
+        );
+        assert_eq!(
+            part_1("R8,U5\nU7,R6a").unwrap_err().to_string(),
+            "2:5: invalid number `6a`"
+        );
+        assert_eq!(
+            part_2("R8,U5").unwrap_err().to_string(),
+            "1:6: expected two wires"
         );
+    }
 
-        assert_eq!(part_2(include_str!("../res/3.txt")), 43_258);
+    #[test]
+    fn rejects_wires_that_never_cross() {
+        assert_eq!(part_1("R8,U5\nL3,D4"), Err(no_crossing()));
+        assert_eq!(part_2("R8,U5\nL3,D4"), Err(no_crossing()));
     }
 }
@@ -1,3 +1,6 @@
+use crate::parse::{self, ParseError};
+use crate::solution::{Error, Solution};
+
 pub fn part_1(min: u32, max: u32) -> usize {
     (min..=max).filter(|&c| validate(c, false)).count()
 }
@@ -6,10 +9,35 @@ pub fn part_2(min: u32, max: u32) -> usize {
     (min..=max).filter(|&c| validate(c, true)).count()
 }
 
+fn parse_range(s: &str) -> Result<(u32, u32), ParseError> {
+    let mut bounds = s.trim().splitn(2, '-');
+
+    match (bounds.next(), bounds.next()) {
+        (Some(min), Some(max)) => Ok((parse_bound(s, min)?, parse_bound(s, max)?)),
+        _ => Err(ParseError::at(
+            s,
+            s,
+            format!("expected `<min>-<max>`, found `{}`", s),
+        )),
+    }
+}
+
+/// Parses a bound of the range, which must be a six-digit number like the passwords.
+fn parse_bound(s: &str, bound: &str) -> Result<u32, ParseError> {
+    match parse::number(s, bound)? {
+        n @ 100_000..=999_999 => Ok(n),
+        _ => Err(ParseError::at(
+            s,
+            bound,
+            format!("`{}` is not a six-digit number", bound),
+        )),
+    }
+}
+
 fn validate(mut c: u32, check_groups: bool) -> bool {
-    let mut last = std::u32::MAX;
+    let mut last = u32::MAX;
 
-    let mut batches = vec![0, 0, 0, 0, 0, 0];
+    let mut batches = [0, 0, 0, 0, 0, 0];
     let mut batch_size = 1;
 
     while c != 0 {
@@ -38,6 +66,27 @@ fn validate(mut c: u32, check_groups: bool) -> bool {
     }
 }
 
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn part_1(input: &str) -> Result<usize, Error> {
+        parse_range(input)
+            .map(|(min, max)| part_1(min, max))
+            .map_err(Error::from)
+    }
+
+    fn part_2(input: &str) -> Option<Result<usize, Error>> {
+        Some(
+            parse_range(input)
+                .map(|(min, max)| part_2(min, max))
+                .map_err(Error::from),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,4 +100,17 @@ mod tests {
     fn part_2_works() {
         assert_eq!(part_2(136_760, 595_730), 1264);
     }
+
+    #[test]
+    fn rejects_bad_ranges() {
+        assert_eq!(
+            parse_range("136760-1595730").unwrap_err().to_string(),
+            "1:8: `1595730` is not a six-digit number"
+        );
+        assert_eq!(
+            parse_range("99999-595730").unwrap_err().to_string(),
+            "1:1: `99999` is not a six-digit number"
+        );
+        assert_eq!(parse_range("136760-595730\n"), Ok((136_760, 595_730)));
+    }
 }
@@ -1,30 +1,64 @@
-use crate::intcode::Intcode;
+use crate::intcode::{missing_output, Intcode};
+use crate::solution::{Error, Solution};
 
-pub fn part_1(s: &str) -> i64 {
-    let out = Intcode::new(s).inputs(&[1]).run().0;
+pub fn part_1(s: &str) -> Result<i64, Error> {
+    let out = Intcode::try_new(s)?.inputs(&[1]).run().0;
+    let (&code, tests) = out.split_last().ok_or_else(missing_output)?;
 
-    if out[..out.len() - 2].iter().any(|e| *e != 0) {
-        panic!("non-zero intermediate results");
+    if let Some(failed) = tests.iter().position(|&e| e != 0) {
+        return Err(Error::unsolvable(format!(
+            "diagnostic test {} failed",
+            failed + 1
+        )));
     }
 
-    *out.last().unwrap() as i64
+    Ok(code as i64)
 }
 
-pub fn part_2(s: &str) -> i64 {
-    Intcode::new(s).inputs(&[5]).run().0[0] as i64
+pub fn part_2(s: &str) -> Result<i64, Error> {
+    let out = Intcode::try_new(s)?.inputs(&[5]).run().0;
+
+    out.first().map(|&w| w as i64).ok_or_else(missing_output)
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = i64;
+    type Part2 = i64;
+
+    fn part_1(input: &str) -> Result<i64, Error> {
+        part_1(input)
+    }
+
+    fn part_2(input: &str) -> Option<Result<i64, Error>> {
+        Some(part_2(input))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/5.txt")), 15_097_178);
+        assert_eq!(part_1(&input::load(5).unwrap()), Ok(15_097_178));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/5.txt")), 1_558_663);
+        assert_eq!(part_2(&input::load(5).unwrap()), Ok(1_558_663));
+    }
+
+    #[test]
+    fn rejects_silent_programs() {
+        let silent = "99";
+        assert_eq!(part_1(silent), Err(missing_output()));
+        assert_eq!(part_2(silent), Err(missing_output()));
+        assert_eq!(
+            part_1("104,1,104,0,99").unwrap_err().to_string(),
+            "no solution: diagnostic test 1 failed"
+        );
     }
 }
@@ -1,30 +1,44 @@
+use crate::parse::ParseError;
+use crate::solution::{Error, Solution};
+
 use std::collections::HashMap;
 
 type OrbitMap<'a> = HashMap<&'a str, &'a str>;
 
-pub fn part_1(s: &str) -> u64 {
-    let map = parse_orbit_map(s);
+pub fn part_1(s: &str) -> Result<u64, ParseError> {
+    let map = parse_orbit_map(s)?;
 
-    map.keys().map(|k| path_to_com(&map, k).len() as u64).sum()
+    Ok(map.keys().map(|k| path_to_com(&map, k).len() as u64).sum())
 }
 
-pub fn part_2(s: &str) -> u64 {
-    let map = parse_orbit_map(s);
+pub fn part_2(s: &str) -> Result<u64, Error> {
+    let map = parse_orbit_map(s)?;
+
+    for body in ["YOU", "SAN"].iter() {
+        if !map.contains_key(body) {
+            return Err(Error::unsolvable(format!(
+                "no object `{}` in the map",
+                body
+            )));
+        }
+    }
 
     let you = path_to_com(&map, "YOU");
     let santa = path_to_com(&map, "SAN");
 
-    let mut min_orbits = std::usize::MAX;
+    let mut min_orbits = None;
 
     for (i, src) in you.iter().enumerate() {
         for (j, dst) in santa.iter().enumerate() {
-            if src == dst && i + j < min_orbits {
-                min_orbits = i + j;
+            if src == dst && min_orbits.is_none_or(|min| i + j < min) {
+                min_orbits = Some(i + j);
             }
         }
     }
 
-    min_orbits as u64
+    min_orbits
+        .map(|min| min as u64)
+        .ok_or_else(|| Error::unsolvable("`YOU` and `SAN` orbit nothing in common"))
 }
 
 fn path_to_com<'a>(map: &'a OrbitMap<'a>, mut body: &'a str) -> Vec<&'a str> {
@@ -37,30 +51,67 @@ fn path_to_com<'a>(map: &'a OrbitMap<'a>, mut body: &'a str) -> Vec<&'a str> {
     path
 }
 
-fn parse_orbit_map(s: &str) -> OrbitMap {
+fn parse_orbit_map(s: &str) -> Result<OrbitMap<'_>, ParseError> {
     s.lines()
-        .fold(HashMap::<&str, &str>::new(), |mut map, orbit| {
-            let mut orbit = orbit.split(')');
-            let a = orbit.next().unwrap();
-            let b = orbit.next().unwrap();
-            map.insert(b, a);
-            map
+        .map(|orbit| {
+            let mut bodies = orbit.splitn(2, ')');
+            match (bodies.next(), bodies.next()) {
+                (Some(a), Some(b)) if !a.is_empty() && !b.is_empty() => Ok((b, a)),
+                _ => Err(ParseError::at(
+                    s,
+                    orbit,
+                    format!("expected `A)B`, found `{}`", orbit),
+                )),
+            }
         })
+        .collect()
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = u64;
+    type Part2 = u64;
+
+    fn part_1(input: &str) -> Result<u64, Error> {
+        part_1(input).map_err(Error::from)
+    }
+
+    fn part_2(input: &str) -> Option<Result<u64, Error>> {
+        Some(part_2(input))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/6-ex0.txt")), 42);
-        assert_eq!(part_1(include_str!("../res/6.txt")), 261_306);
+        assert_eq!(part_1(include_str!("../res/6-ex0.txt")), Ok(42));
+        assert_eq!(part_1(&input::load(6).unwrap()), Ok(261_306));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/6-ex1.txt")), 4);
-        assert_eq!(part_2(include_str!("../res/6.txt")), 382);
+        assert_eq!(part_2(include_str!("../res/6-ex1.txt")), Ok(4));
+        assert_eq!(part_2(&input::load(6).unwrap()), Ok(382));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(
+            part_1("COM)B\nB-C").unwrap_err().to_string(),
+            "2:1: expected `A)B`, found `B-C`"
+        );
+        assert_eq!(
+            part_2("COM)B").unwrap_err().to_string(),
+            "no solution: no object `YOU` in the map"
+        );
+        assert_eq!(
+            part_2("COM)YOU\nX)SAN").unwrap_err().to_string(),
+            "no solution: `YOU` and `SAN` orbit nothing in common"
+        );
     }
 }
@@ -1,24 +1,26 @@
-use itertools::Itertools;
+use crate::intcode::{missing_output, Intcode, StopCondition};
+use crate::solution::{Error, Solution};
 
-use crate::intcode::{Intcode, StopCondition};
+use itertools::Itertools;
 
-pub fn part_1(s: &str) -> i64 {
-    let ic = Intcode::new(s);
+pub fn part_1(s: &str) -> Result<i64, Error> {
+    let ic = Intcode::try_new(s)?;
     let mut thrust = 0;
 
     for phases in (0..5).permutations(5) {
         let mut output = 0;
 
         for phase in phases.iter() {
-            output = *ic.clone().inputs(&[*phase, output]).run().0.last().unwrap();
+            let (outs, _) = ic.clone().inputs(&[*phase, output]).run();
+            output = *outs.last().ok_or_else(missing_output)?;
         }
         thrust = thrust.max(output);
     }
-    thrust as i64
+    Ok(thrust as i64)
 }
 
-pub fn part_2(s: &str) -> i64 {
-    let ic = Intcode::new(s);
+pub fn part_2(s: &str) -> Result<i64, Error> {
+    let ic = Intcode::try_new(s)?;
     let mut thrust = 0;
 
     for phases in (5..10).permutations(5) {
@@ -35,26 +37,42 @@ pub fn part_2(s: &str) -> i64 {
 
                 let (outs, cnd) = ic.run();
 
-                output = *outs.last().unwrap();
+                output = *outs.last().ok_or_else(missing_output)?;
                 stop = cnd;
             }
         }
         thrust = thrust.max(output);
     }
-    thrust as i64
+    Ok(thrust as i64)
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = i64;
+    type Part2 = i64;
+
+    fn part_1(input: &str) -> Result<i64, Error> {
+        part_1(input)
+    }
+
+    fn part_2(input: &str) -> Option<Result<i64, Error>> {
+        Some(part_2(input))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/7.txt")), 118_936);
+        assert_eq!(part_1(&input::load(7).unwrap()), Ok(118_936));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/7.txt")), 57_660_948);
+        assert_eq!(part_2(&input::load(7).unwrap()), Ok(57_660_948));
     }
 }
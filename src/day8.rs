@@ -1,55 +1,93 @@
-use std::convert::TryInto;
+use crate::grid::Grid;
+use crate::ocr;
+use crate::parse::ParseError;
+use crate::solution::{Error, Solution};
 
 use itertools::Itertools;
 
+use std::convert::TryInto;
+
 const W: usize = 25;
 const H: usize = 6;
 
-pub fn part_1(s: &str) -> u64 {
-    let layer = s
-        .as_bytes()
+pub fn part_1(s: &str) -> Result<u64, ParseError> {
+    let layer = parse_image(s)?
         .chunks(W * H)
         .minmax_by(|a, b| bytecount::count(a, b'0').cmp(&bytecount::count(b, b'0')))
         .into_option()
         .unwrap()
         .0;
 
-    (bytecount::count(layer, b'1') * bytecount::count(layer, b'2'))
-        .try_into()
-        .unwrap()
+    Ok(
+        (bytecount::count(layer, b'1') * bytecount::count(layer, b'2'))
+            .try_into()
+            .unwrap(),
+    )
 }
 
-pub fn part_2(s: &str) -> &str {
-    let layers = s.as_bytes().chunks(W * H).collect::<Vec<_>>();
-
-    for h in 0..H {
-        for w in 0..W {
-            for layer in layers.iter() {
-                let c = layer[h * W + w];
-                if c != b'2' {
-                    print!("{}", if c == b'1' { '*' } else { ' ' });
-                    break;
-                }
-            }
+pub fn part_2(s: &str) -> Result<String, Error> {
+    let layers = parse_image(s)?.chunks(W * H).collect::<Vec<_>>();
+    let mut image = Grid::new(W, H, false);
+
+    for y in 0..H {
+        for x in 0..W {
+            // The first layer that isn't transparent decides the colour
+            let c = layers
+                .iter()
+                .map(|layer| layer[y * W + x])
+                .find(|&c| c != b'2');
+            image[(x, y)] = c == Some(b'1');
         }
-        println!();
     }
 
-    // I can't be arsed to parse the above image
-    "ZBJAB"
+    ocr::read(&image).map_err(|e| Error::unsolvable(e.to_string()))
+}
+
+fn parse_image(s: &str) -> Result<&[u8], ParseError> {
+    if let Some(i) = s.find(|c| !('0'..='2').contains(&c)) {
+        return Err(ParseError::at(
+            s,
+            &s[i..],
+            format!("invalid pixel `{}`", s[i..].chars().next().unwrap()),
+        ));
+    }
+    if s.is_empty() || !s.len().is_multiple_of(W * H) {
+        return Err(ParseError::at_end(
+            s,
+            format!("expected whole {}x{} layers", W, H),
+        ));
+    }
+
+    Ok(s.as_bytes())
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = u64;
+    type Part2 = String;
+
+    fn part_1(input: &str) -> Result<u64, Error> {
+        part_1(input).map_err(Error::from)
+    }
+
+    fn part_2(input: &str) -> Option<Result<String, Error>> {
+        Some(part_2(input))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/8.txt")), 2806);
+        assert_eq!(part_1(&input::load(8).unwrap()), Ok(2806));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/8.txt")), "ZBJAB");
+        assert_eq!(part_2(&input::load(8).unwrap()), Ok("ZBJAB".to_string()));
     }
 }
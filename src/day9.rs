@@ -1,24 +1,45 @@
-use crate::intcode::Intcode;
+use crate::intcode::{missing_output, Intcode};
+use crate::solution::{Error, Solution};
 
-pub fn part_1(s: &str) -> i128 {
-    Intcode::new(s).inputs(&[1]).run().0[0]
+pub fn part_1(s: &str) -> Result<i128, Error> {
+    let out = Intcode::try_new(s)?.inputs(&[1]).run().0;
+
+    out.first().copied().ok_or_else(missing_output)
 }
 
-pub fn part_2(s: &str) -> i128 {
-    Intcode::new(s).inputs(&[2]).run().0[0]
+pub fn part_2(s: &str) -> Result<i128, Error> {
+    let out = Intcode::try_new(s)?.inputs(&[2]).run().0;
+
+    out.first().copied().ok_or_else(missing_output)
+}
+
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    type Part1 = i128;
+    type Part2 = i128;
+
+    fn part_1(input: &str) -> Result<i128, Error> {
+        part_1(input)
+    }
+
+    fn part_2(input: &str) -> Option<Result<i128, Error>> {
+        Some(part_2(input))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input;
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(include_str!("../res/9.txt")), 3_345_854_957);
+        assert_eq!(part_1(&input::load(9).unwrap()), Ok(3_345_854_957));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(part_2(include_str!("../res/9.txt")), 68938);
+        assert_eq!(part_2(&input::load(9).unwrap()), Ok(68938));
     }
 }
@@ -0,0 +1,521 @@
+pub mod oracle;
+
+use crate::rng::Rng;
+
+use std::collections::{HashSet, VecDeque};
+
+type Pos = (i64, i64);
+type Segment = (Pos, Pos);
+
+const DIRS: [Pos; 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+/// Two wires of `moves` moves each, none longer than `max_len`, that cross away from the
+/// origin.
+///
+/// The solver only sees crossings of a horizontal and a vertical segment, so the wires
+/// never run along each other: the first one only turns on even coordinates, and the
+/// second one, past its first move, only on odd ones.
+pub fn wires(seed: u64, moves: usize, max_len: i64) -> String {
+    assert!(moves >= 2 && max_len >= 2);
+    let mut rng = Rng::new(seed);
+
+    loop {
+        let fst = wire(&mut rng, moves, max_len, false);
+        let snd = wire(&mut rng, moves, max_len, true);
+        let (a, b) = (segments(&fst), segments(&snd));
+
+        let overlap = a.iter().any(|s| b.iter().any(|t| overlaps(s, t)));
+        let cross = a.iter().any(|s| {
+            b.iter()
+                .any(|t| crossing(s, t).is_some_and(|p| p != (0, 0)))
+        });
+
+        if !overlap && cross {
+            let format = |w: &[(char, i64)]| {
+                w.iter()
+                    .map(|(dir, len)| format!("{}{}", dir, len))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            };
+            return format!("{}\n{}", format(&fst), format(&snd));
+        }
+    }
+}
+
+fn wire(rng: &mut Rng, moves: usize, max_len: i64, odd: bool) -> Vec<(char, i64)> {
+    (0..moves)
+        .map(|i| {
+            let dir = match ((i % 2 == 0) != odd, rng.chance(50)) {
+                (true, true) => 'U',
+                (true, false) => 'D',
+                (false, true) => 'R',
+                (false, false) => 'L',
+            };
+            let len = if odd && i < 2 {
+                2 * rng.range(0, (max_len - 1) / 2) + 1
+            } else {
+                2 * rng.range(1, max_len / 2)
+            };
+            (dir, len)
+        })
+        .collect()
+}
+
+fn segments(wire: &[(char, i64)]) -> Vec<Segment> {
+    let mut pos = (0, 0);
+
+    wire.iter()
+        .map(|&(dir, len)| {
+            let start = pos;
+            match dir {
+                'U' => pos.1 += len,
+                'D' => pos.1 -= len,
+                'R' => pos.0 += len,
+                _ => pos.0 -= len,
+            }
+            (start, pos)
+        })
+        .collect()
+}
+
+fn span(a: i64, b: i64) -> (i64, i64) {
+    (a.min(b), a.max(b))
+}
+
+fn overlaps(&(s0, s1): &Segment, &(t0, t1): &Segment) -> bool {
+    let along = |a: (i64, i64), b: (i64, i64)| a.0 <= b.1 && b.0 <= a.1;
+
+    (s0.1 == s1.1 && t0.1 == t1.1 && s0.1 == t0.1 && along(span(s0.0, s1.0), span(t0.0, t1.0)))
+        || (s0.0 == s1.0
+            && t0.0 == t1.0
+            && s0.0 == t0.0
+            && along(span(s0.1, s1.1), span(t0.1, t1.1)))
+}
+
+fn crossing(&(s0, s1): &Segment, &(t0, t1): &Segment) -> Option<Pos> {
+    let within = |v: i64, (lo, hi): (i64, i64)| lo <= v && v <= hi;
+
+    if s0.1 == s1.1 && t0.0 == t1.0 {
+        Some((t0.0, s0.1))
+            .filter(|&(x, y)| within(x, span(s0.0, s1.0)) && within(y, span(t0.1, t1.1)))
+    } else if s0.0 == s1.0 && t0.1 == t1.1 {
+        crossing(&(t0, t1), &(s0, s1))
+    } else {
+        None
+    }
+}
+
+/// A `width` x `height` map where each cell holds an asteroid with a `density` percent
+/// chance.
+pub fn asteroids(seed: u64, width: usize, height: usize, density: u64) -> String {
+    let mut rng = Rng::new(seed);
+
+    (0..height)
+        .map(|_| {
+            (0..width)
+                .map(|_| if rng.chance(density) { '#' } else { '.' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Reactions making `FUEL` from `chemicals` other chemicals, in no particular order. As in
+/// the puzzle, each chemical comes from exactly one reaction, and is made either from ore
+/// alone or from chemicals listed before it, so there are no cycles.
+pub fn reactions(seed: u64, chemicals: usize) -> String {
+    assert!(chemicals >= 1);
+    let mut rng = Rng::new(seed);
+    let names = names(&mut rng, chemicals, 1, 5);
+    let raw = chemicals.div_ceil(3);
+
+    let mut used = vec![false; chemicals];
+    let mut lines = vec![];
+
+    for (i, name) in names.iter().enumerate() {
+        let inputs = if i < raw {
+            format!("{} ORE", rng.range(1, 200))
+        } else {
+            let mut earlier = (0..i).collect::<Vec<_>>();
+            rng.shuffle(&mut earlier);
+            earlier.truncate(rng.range(1, 3) as usize);
+
+            ingredients(&mut rng, &earlier, &names, &mut used)
+        };
+        lines.push(format!("{} => {} {}", inputs, rng.range(1, 10), name));
+    }
+
+    let unused = (0..chemicals).filter(|&i| !used[i]).collect::<Vec<_>>();
+    let inputs = ingredients(&mut rng, &unused, &names, &mut used);
+    lines.push(format!("{} => 1 FUEL", inputs));
+
+    rng.shuffle(&mut lines);
+    lines.join("\n")
+}
+
+fn ingredients(rng: &mut Rng, inputs: &[usize], names: &[String], used: &mut [bool]) -> String {
+    inputs
+        .iter()
+        .map(|&j| {
+            used[j] = true;
+            format!("{} {}", rng.range(1, 9), names[j])
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `n` distinct names of uppercase letters, other than the ones the puzzles reserve.
+fn names(rng: &mut Rng, n: usize, min_len: i64, max_len: i64) -> Vec<String> {
+    let reserved = ["ORE", "FUEL", "AA", "ZZ"];
+    let mut names = Vec::with_capacity(n);
+    let mut seen = HashSet::new();
+
+    while names.len() < n {
+        let name = (0..rng.range(min_len, max_len))
+            .map(|_| (b'A' + rng.below(26) as u8) as char)
+            .collect::<String>();
+
+        if !reserved.contains(&name.as_str()) && seen.insert(name.clone()) {
+            names.push(name);
+        }
+    }
+
+    names
+}
+
+/// A `width` x `height` vault with `keys` keys and doors for most of them, placed so that
+/// every key can be collected. As in the puzzle, the corridors form a tree.
+pub fn vault(seed: u64, width: usize, height: usize, keys: usize) -> String {
+    assert!(width % 2 == 1 && height % 2 == 1 && width >= 5 && height >= 5);
+    assert!(keys <= 26);
+    let (w, h) = (width as i64, height as i64);
+    let mut rng = Rng::new(seed);
+    let mut grid = vec![vec![b'#'; width]; height];
+
+    let room = |(x, y): Pos| x > 0 && y > 0 && x < w - 1 && y < h - 1 && x % 2 == 1 && y % 2 == 1;
+    carve(&mut rng, &mut grid, (1, 1), room);
+
+    let mut rooms = (0..h)
+        .flat_map(|y| (0..w).map(move |x| (x, y)))
+        .filter(|&p| room(p))
+        .collect::<Vec<_>>();
+    assert!(rooms.len() > keys, "no room for {} keys", keys);
+    rng.shuffle(&mut rooms);
+
+    set(&mut grid, rooms[0], b'@');
+    for (k, &pos) in rooms[1..=keys].iter().enumerate() {
+        set(&mut grid, pos, b'a' + k as u8);
+    }
+
+    for k in 0..keys {
+        if !rng.chance(75) {
+            continue;
+        }
+        for _ in 0..10 {
+            let open = find(&grid, b'.');
+            let pos = *rng.choose(&open);
+
+            set(&mut grid, pos, b'A' + k as u8);
+            if solvable(&grid, keys) {
+                break;
+            }
+            set(&mut grid, pos, b'.');
+        }
+    }
+
+    render(&grid)
+}
+
+/// Whether every key can be collected, by repeatedly picking up whatever keys are
+/// reachable with the ones already held.
+fn solvable(grid: &[Vec<u8>], keys: usize) -> bool {
+    let start = find(grid, b'@')[0];
+    let mut held = 0u32;
+
+    loop {
+        let mut found = held;
+        let mut seen = HashSet::new();
+        let mut q = VecDeque::new();
+
+        seen.insert(start);
+        q.push_back(start);
+
+        while let Some((x, y)) = q.pop_front() {
+            for &(dx, dy) in DIRS.iter() {
+                let next = (x + dx, y + dy);
+
+                match get(grid, next) {
+                    b'#' => continue,
+                    c @ b'A'..=b'Z' if held & (1 << (c - b'A')) == 0 => continue,
+                    c @ b'a'..=b'z' => found |= 1 << (c - b'a'),
+                    _ => (),
+                }
+                if seen.insert(next) {
+                    q.push_back(next);
+                }
+            }
+        }
+
+        if found == held {
+            return held == (1 << keys) - 1;
+        }
+        held = found;
+    }
+}
+
+/// A donut maze with a `width` x `height` body, a ring `thickness` tiles wide and
+/// `portals` pairs of portals besides `AA` and `ZZ`. Each pair joins the outer edge to the
+/// inner one, and the ring is connected, so `ZZ` can be reached without portals.
+pub fn donut(seed: u64, width: usize, height: usize, thickness: usize, portals: usize) -> String {
+    let (w, h, t) = (width as i64, height as i64, thickness as i64);
+    assert!(w % 2 == 1 && h % 2 == 1 && t % 2 == 1 && t >= 3);
+    assert!(
+        w - 2 * t >= 7 && h - 2 * t >= 7,
+        "the hole is too small for labels"
+    );
+    let mut rng = Rng::new(seed);
+    let mut body = vec![vec![b'#'; width]; height];
+
+    let ring = |(x, y): Pos| {
+        x >= 0 && y >= 0 && x < w && y < h && (x < t || y < t || x >= w - t || y >= h - t)
+    };
+    carve(&mut rng, &mut body, (0, 0), |(x, y)| {
+        ring((x, y)) && x % 2 == 0 && y % 2 == 0
+    });
+
+    // Knock down a few more walls between rooms, for loops
+    for y in 0..h {
+        for x in 0..w {
+            let (a, b) = if x % 2 == 1 && y % 2 == 0 {
+                ((x - 1, y), (x + 1, y))
+            } else if x % 2 == 0 && y % 2 == 1 {
+                ((x, y - 1), (x, y + 1))
+            } else {
+                continue;
+            };
+            if ring(a) && ring(b) && ring((x, y)) && rng.chance(10) {
+                set(&mut body, (x, y), b'.');
+            }
+        }
+    }
+
+    // Rooms on the edges, with the direction their labels go in
+    let mut outer = vec![];
+    for x in (2..w - 2).step_by(2) {
+        outer.push(((x, 0), (0, -1)));
+        outer.push(((x, h - 1), (0, 1)));
+    }
+    for y in (2..h - 2).step_by(2) {
+        outer.push(((0, y), (-1, 0)));
+        outer.push(((w - 1, y), (1, 0)));
+    }
+
+    // Inner labels keep clear of the hole's corners, where they'd run into each other
+    let mut inner = vec![];
+    for x in (t + 3..w - t - 3).step_by(2) {
+        inner.push(((x, t - 1), (0, 1)));
+        inner.push(((x, h - t), (0, -1)));
+    }
+    for y in (t + 3..h - t - 3).step_by(2) {
+        inner.push(((t - 1, y), (1, 0)));
+        inner.push(((w - t, y), (-1, 0)));
+    }
+
+    assert!(
+        outer.len() >= portals + 2 && inner.len() >= portals,
+        "no room for {} portals",
+        portals
+    );
+    rng.shuffle(&mut outer);
+    rng.shuffle(&mut inner);
+
+    let mut text = vec![vec![b' '; width + 4]; height + 4];
+    for (y, row) in body.iter().enumerate() {
+        text[y + 2][2..width + 2].copy_from_slice(row);
+    }
+
+    let mut label = |(pos, dir): (Pos, Pos), name: &[u8]| {
+        let near = (pos.0 + dir.0 + 2, pos.1 + dir.1 + 2);
+        let far = (pos.0 + 2 * dir.0 + 2, pos.1 + 2 * dir.1 + 2);
+        let (first, second) = if dir.0 + dir.1 > 0 {
+            (near, far)
+        } else {
+            (far, near)
+        };
+
+        set(&mut text, first, name[0]);
+        set(&mut text, second, name[1]);
+    };
+
+    label(outer[0], b"AA");
+    label(outer[1], b"ZZ");
+    for (i, name) in names(&mut rng, portals, 2, 2).iter().enumerate() {
+        label(outer[i + 2], name.as_bytes());
+        label(inner[i], name.as_bytes());
+    }
+
+    render(&text)
+}
+
+/// Carves a maze into `grid` with a randomised depth-first search, joining every room to
+/// its neighbours two tiles away through the tile between them.
+fn carve(rng: &mut Rng, grid: &mut [Vec<u8>], start: Pos, room: impl Fn(Pos) -> bool) {
+    let mut stack = vec![start];
+    set(grid, start, b'.');
+
+    while let Some(&(x, y)) = stack.last() {
+        let next = DIRS
+            .iter()
+            .map(|&(dx, dy)| ((x + 2 * dx, y + 2 * dy), (x + dx, y + dy)))
+            .filter(|&(to, _)| room(to) && get(grid, to) == b'#')
+            .collect::<Vec<_>>();
+
+        if next.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let (to, between) = *rng.choose(&next);
+        set(grid, between, b'.');
+        set(grid, to, b'.');
+        stack.push(to);
+    }
+}
+
+fn get(grid: &[Vec<u8>], (x, y): Pos) -> u8 {
+    grid[y as usize][x as usize]
+}
+
+fn set(grid: &mut [Vec<u8>], (x, y): Pos, c: u8) {
+    grid[y as usize][x as usize] = c;
+}
+
+fn find(grid: &[Vec<u8>], c: u8) -> Vec<Pos> {
+    grid.iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(move |&(_, &t)| t == c)
+                .map(move |(x, _)| (x as i64, y as i64))
+        })
+        .collect()
+}
+
+fn render(grid: &[Vec<u8>]) -> String {
+    grid.iter()
+        .map(|row| String::from_utf8_lossy(row).into_owned())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `len` shuffles of the sizes found in the puzzle input.
+pub fn shuffles(seed: u64, len: usize) -> String {
+    let mut rng = Rng::new(seed);
+
+    (0..len)
+        .map(|_| match rng.below(3) {
+            0 => "deal into new stack".to_string(),
+            1 => format!("cut {}", rng.range(-9999, 9999)),
+            _ => format!("deal with increment {}", rng.range(2, 75)),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{day10, day14, day18, day20, day22, day3};
+
+    #[test]
+    fn reproducible() {
+        assert_eq!(vault(7, 15, 9, 4), vault(7, 15, 9, 4));
+        assert_ne!(vault(7, 15, 9, 4), vault(8, 15, 9, 4));
+        assert_eq!(reactions(7, 10), reactions(7, 10));
+    }
+
+    #[test]
+    fn wires_match_oracle() {
+        for seed in 0..100 {
+            let s = wires(seed, 8, 12);
+            let (dist, steps) = oracle::day3(&s);
+
+            assert_eq!(day3::part_1(&s), Ok(dist as i32), "seed {}:\n{}", seed, s);
+            assert_eq!(day3::part_2(&s), Ok(steps as i32), "seed {}:\n{}", seed, s);
+        }
+    }
+
+    #[test]
+    fn asteroids_match_oracle() {
+        for seed in 0..30 {
+            let s = asteroids(seed, 12, 10, 30);
+            assert_eq!(
+                day10::part_1(&s),
+                Ok(oracle::day10(&s)),
+                "seed {}:\n{}",
+                seed,
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn reactions_match_oracle() {
+        for seed in 0..100 {
+            let s = reactions(seed, 8);
+            let (ore, fuel) = oracle::day14(&s);
+
+            assert_eq!(day14::part_1(&s), Ok(ore), "seed {}:\n{}", seed, s);
+            assert_eq!(day14::part_2(&s), Ok(fuel), "seed {}:\n{}", seed, s);
+        }
+    }
+
+    #[test]
+    fn vaults_match_oracle() {
+        for seed in 0..30 {
+            let keys = seed as usize % 6 + 1;
+            let s = vault(seed, 13, 11, keys);
+            let steps = oracle::day18(&s) as isize;
+
+            assert_eq!(day18::part_1(&s, keys), Ok(steps), "seed {}:\n{}", seed, s);
+        }
+    }
+
+    #[test]
+    fn donuts_match_oracle() {
+        for seed in 0..30 {
+            let s = donut(seed, 19, 17, 3, 4);
+            let (flat, recursive) = oracle::day20(&s);
+
+            assert_eq!(
+                day20::part_1(&s),
+                Ok(flat as isize),
+                "seed {}:\n{}",
+                seed,
+                s
+            );
+            assert_eq!(
+                day20::part_2(&s),
+                Ok(recursive as isize),
+                "seed {}:\n{}",
+                seed,
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn shuffles_match_oracle() {
+        for seed in 0..20 {
+            let s = shuffles(seed, 30);
+            assert_eq!(
+                day22::part_1(&s),
+                Ok(oracle::day22(&s)),
+                "seed {}:\n{}",
+                seed,
+                s
+            );
+        }
+    }
+}
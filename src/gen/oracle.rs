@@ -0,0 +1,315 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+type Pos = (i64, i64);
+type Reactions<'a> = HashMap<&'a str, (u128, Vec<(u128, &'a str)>)>;
+
+const DIRS: [Pos; 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+/// The closest crossing to the origin and the fewest combined steps to a crossing, found
+/// by walking both wires one cell at a time.
+pub fn day3(s: &str) -> (i64, i64) {
+    let walks = s.lines().map(walk).collect::<Vec<_>>();
+    let crossings = walks[0]
+        .iter()
+        .filter_map(|(pos, steps)| walks[1].get(pos).map(|other| (*pos, steps + other)))
+        .filter(|&(pos, _)| pos != (0, 0))
+        .collect::<Vec<_>>();
+
+    (
+        crossings
+            .iter()
+            .map(|((x, y), _)| x.abs() + y.abs())
+            .min()
+            .unwrap(),
+        crossings.iter().map(|&(_, steps)| steps).min().unwrap(),
+    )
+}
+
+/// Every cell a wire visits, with the steps taken to first reach it.
+fn walk(wire: &str) -> HashMap<Pos, i64> {
+    let mut cells = HashMap::new();
+    let (mut pos, mut steps) = ((0, 0), 0);
+
+    for m in wire.split(',') {
+        let (dx, dy) = match &m[..1] {
+            "U" => (0, 1),
+            "D" => (0, -1),
+            "R" => (1, 0),
+            "L" => (-1, 0),
+            _ => unreachable!(),
+        };
+        for _ in 0..m[1..].parse::<i64>().unwrap() {
+            pos = (pos.0 + dx, pos.1 + dy);
+            steps += 1;
+            cells.entry(pos).or_insert(steps);
+        }
+    }
+
+    cells
+}
+
+/// The most asteroids seen from one asteroid, checking every third asteroid for whether it
+/// blocks the view.
+pub fn day10(s: &str) -> usize {
+    let asteroids = s
+        .lines()
+        .enumerate()
+        .flat_map(|(y, line)| {
+            line.char_indices()
+                .filter(|&(_, c)| c == '#')
+                .map(move |(x, _)| (x as i64, y as i64))
+        })
+        .collect::<Vec<_>>();
+
+    let blocks = |a: Pos, b: Pos, c: Pos| {
+        let (ab, ac) = ((b.0 - a.0, b.1 - a.1), (c.0 - a.0, c.1 - a.1));
+        let dot = ab.0 * ac.0 + ab.1 * ac.1;
+
+        ab.0 * ac.1 == ab.1 * ac.0 && dot > 0 && dot < ab.0 * ab.0 + ab.1 * ab.1
+    };
+
+    asteroids
+        .iter()
+        .map(|&a| {
+            asteroids
+                .iter()
+                .filter(|&&b| b != a)
+                .filter(|&&b| !asteroids.iter().any(|&c| c != b && blocks(a, b, c)))
+                .count()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// The ore needed for one fuel, and the most fuel a trillion ore makes.
+pub fn day14(s: &str) -> (u128, u128) {
+    let ore = |fuel| fuel_cost(s, fuel);
+    let (mut lo, mut hi) = (0u128, 1_000_000_000_000);
+
+    while lo < hi {
+        let mid = (lo + hi).div_ceil(2);
+        if ore(mid) <= 1_000_000_000_000 {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+
+    (ore(1), lo)
+}
+
+/// The ore needed for `fuel` fuel, running each reaction only once all of its consumers
+/// have added their demand.
+pub fn fuel_cost(s: &str, fuel: u128) -> u128 {
+    let mut reactions = HashMap::new();
+
+    for line in s.lines() {
+        let mut sides = line.split(" => ");
+        let (lhs, rhs) = (sides.next().unwrap(), sides.next().unwrap());
+        let (qty, product) = chemical(rhs);
+        reactions.insert(
+            product,
+            (qty, lhs.split(", ").map(chemical).collect::<Vec<_>>()),
+        );
+    }
+
+    let mut order = vec![];
+    let mut done = HashSet::new();
+    topo_sort("FUEL", &reactions, &mut done, &mut order);
+
+    let mut need = HashMap::new();
+    need.insert("FUEL", fuel);
+
+    for product in order.into_iter().rev() {
+        let (qty, inputs) = &reactions[product];
+        let runs = need.get(product).copied().unwrap_or(0).div_ceil(*qty);
+
+        for &(n, input) in inputs {
+            *need.entry(input).or_insert(0) += runs * n;
+        }
+    }
+
+    need["ORE"]
+}
+
+fn chemical(s: &str) -> (u128, &str) {
+    let mut fields = s.split(' ');
+    (
+        fields.next().unwrap().parse().unwrap(),
+        fields.next().unwrap(),
+    )
+}
+
+/// Orders chemicals so that each comes after everything it's made from.
+fn topo_sort<'a>(
+    product: &'a str,
+    reactions: &Reactions<'a>,
+    done: &mut HashSet<&'a str>,
+    order: &mut Vec<&'a str>,
+) {
+    if product == "ORE" || !done.insert(product) {
+        return;
+    }
+    for &(_, input) in reactions[product].1.iter() {
+        topo_sort(input, reactions, done, order);
+    }
+    order.push(product);
+}
+
+/// The fewest steps to collect every key, by breadth-first search over positions and the
+/// set of keys held.
+pub fn day18(s: &str) -> usize {
+    let grid = s.lines().map(str::as_bytes).collect::<Vec<_>>();
+    let keys = s.bytes().filter(u8::is_ascii_lowercase).count();
+    let all = (1u32 << keys) - 1;
+
+    let start = find(&grid, |c| c == b'@')[0];
+    let mut seen = HashSet::new();
+    let mut q = VecDeque::new();
+
+    seen.insert((start, 0));
+    q.push_back((start, 0u32, 0));
+
+    while let Some((pos, held, steps)) = q.pop_front() {
+        if held == all {
+            return steps;
+        }
+
+        for &(dx, dy) in DIRS.iter() {
+            let next = (pos.0 + dx, pos.1 + dy);
+            let mut held = held;
+
+            match grid[next.1 as usize][next.0 as usize] {
+                b'#' => continue,
+                c @ b'A'..=b'Z' if held & (1 << (c - b'A')) == 0 => continue,
+                c @ b'a'..=b'z' => held |= 1 << (c - b'a'),
+                _ => (),
+            }
+            if seen.insert((next, held)) {
+                q.push_back((next, held, steps + 1));
+            }
+        }
+    }
+
+    unreachable!("the vault can't be solved")
+}
+
+/// The fewest steps from `AA` to `ZZ` through a flat and a recursive donut maze, by
+/// breadth-first search over the grid.
+pub fn day20(s: &str) -> (usize, usize) {
+    let grid = s.lines().map(str::as_bytes).collect::<Vec<_>>();
+    let at = |(x, y): Pos| {
+        grid.get(y as usize)
+            .and_then(|line| line.get(x as usize))
+            .copied()
+            .unwrap_or(b' ')
+    };
+
+    let floors = find(&grid, |c| c == b'.');
+    let body = find(&grid, |c| c == b'.' || c == b'#');
+    let (x0, x1) = (
+        body.iter().map(|p| p.0).min().unwrap(),
+        body.iter().map(|p| p.0).max().unwrap(),
+    );
+    let (y0, y1) = (
+        body.iter().map(|p| p.1).min().unwrap(),
+        body.iter().map(|p| p.1).max().unwrap(),
+    );
+
+    // Each labelled floor, with its label and whether it's on the outer edge
+    let mut portals = HashMap::new();
+    for &pos in floors.iter() {
+        for &(dx, dy) in DIRS.iter() {
+            let (near, far) = ((pos.0 + dx, pos.1 + dy), (pos.0 + 2 * dx, pos.1 + 2 * dy));
+            if at(near).is_ascii_uppercase() {
+                let name = if dx + dy > 0 {
+                    [at(near), at(far)]
+                } else {
+                    [at(far), at(near)]
+                };
+                let outer = pos.0 == x0 || pos.0 == x1 || pos.1 == y0 || pos.1 == y1;
+                portals.insert(pos, (name, outer));
+            }
+        }
+    }
+
+    let named = |name: [u8; 2]| {
+        portals
+            .iter()
+            .filter(move |(_, &(n, _))| n == name)
+            .map(|(&pos, _)| pos)
+    };
+    let start = named(*b"AA").next().unwrap();
+    let end = named(*b"ZZ").next().unwrap();
+
+    let search = |recursive: bool| {
+        let mut seen = HashSet::new();
+        let mut q = VecDeque::new();
+
+        seen.insert((start, 0));
+        q.push_back((start, 0i64, 0));
+
+        while let Some((pos, level, steps)) = q.pop_front() {
+            if (pos, level) == (end, 0) {
+                return steps;
+            }
+
+            let mut next = DIRS
+                .iter()
+                .map(|&(dx, dy)| ((pos.0 + dx, pos.1 + dy), level))
+                .filter(|&(p, _)| at(p) == b'.')
+                .collect::<Vec<_>>();
+
+            if let Some(&(name, outer)) = portals.get(&pos) {
+                let level = match (recursive, outer) {
+                    (false, _) => Some(level),
+                    (true, false) => Some(level + 1),
+                    (true, true) => Some(level - 1).filter(|&l| l >= 0),
+                };
+                if let Some(level) = level {
+                    next.extend(named(name).filter(|&p| p != pos).map(|p| (p, level)));
+                }
+            }
+
+            for state in next {
+                if seen.insert(state) {
+                    q.push_back((state.0, state.1, steps + 1));
+                }
+            }
+        }
+
+        unreachable!("`ZZ` can't be reached")
+    };
+
+    (search(false), search(true))
+}
+
+fn find(grid: &[&[u8]], pred: impl Fn(u8) -> bool) -> Vec<Pos> {
+    grid.iter()
+        .enumerate()
+        .flat_map(|(y, line)| {
+            line.iter()
+                .enumerate()
+                .filter(|&(_, &c)| pred(c))
+                .map(move |(x, _)| (x as i64, y as i64))
+        })
+        .collect()
+}
+
+/// Where card 2019 ends up in a deck of 10007 cards, following only that card.
+pub fn day22(s: &str) -> usize {
+    const N: i64 = 10007;
+
+    let pos = s.lines().fold(2019, |pos, line| {
+        if line == "deal into new stack" {
+            N - 1 - pos
+        } else if let Some(n) = line.strip_prefix("cut ") {
+            (pos - n.parse::<i64>().unwrap()).rem_euclid(N)
+        } else {
+            let n = line["deal with increment ".len()..].parse::<i64>().unwrap();
+            pos * n % N
+        }
+    });
+
+    pos as usize
+}
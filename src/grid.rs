@@ -0,0 +1,388 @@
+use crate::parse::ParseError;
+
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// A position in a sparse grid, which may be negative.
+pub type Point = (i64, i64);
+
+/// North, south, west and east, followed by the diagonals.
+const STEPS: [Point; 8] = [
+    (0, -1),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+
+/// A rectangular map of tiles, indexed by `(x, y)` from the top-left corner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T> {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Parses one tile per character. Characters `tile` doesn't map and lines of different
+    /// lengths are errors.
+    pub fn parse<F>(s: &str, tile: F) -> Result<Grid<T>, ParseError>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        Grid::parse_lines(s, None, tile)
+    }
+
+    /// Like `parse`, but pads short lines with `fill`, e.g. where trailing spaces were cut.
+    pub fn parse_ragged<F>(s: &str, fill: T, tile: F) -> Result<Grid<T>, ParseError>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        Grid::parse_lines(s, Some(fill), tile)
+    }
+
+    fn parse_lines<F>(s: &str, fill: Option<T>, mut tile: F) -> Result<Grid<T>, ParseError>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        let lens = s.lines().map(|line| line.chars().count());
+        let width = match fill {
+            Some(_) => lens.max(),
+            None => lens.take(1).next(),
+        }
+        .unwrap_or(0);
+
+        let mut cells = Vec::new();
+        let mut height = 0;
+
+        for line in s.lines() {
+            let len = line.chars().count();
+            if fill.is_none() && len != width {
+                return Err(ParseError::at(
+                    s,
+                    line,
+                    format!("expected {} columns, found {}", width, len),
+                ));
+            }
+
+            for (i, c) in line.char_indices() {
+                cells.push(tile(c).ok_or_else(|| {
+                    ParseError::at(s, &line[i..], format!("invalid tile `{}`", c))
+                })?);
+            }
+            if let Some(fill) = &fill {
+                cells.extend((len..width).map(|_| fill.clone()));
+            }
+            height += 1;
+        }
+
+        Ok(Grid {
+            width,
+            height,
+            cells,
+        })
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, (x, y): (usize, usize)) -> Option<&T> {
+        if x < self.width && y < self.height {
+            Some(&self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, (x, y): (usize, usize)) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            Some(&mut self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// The orthogonal neighbours of `pos` that are inside the grid, in the order north,
+    /// south, west, east.
+    pub fn neighbours(&self, pos: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.steps(pos, 4)
+    }
+
+    /// Like `neighbours`, but with the diagonal ones after the orthogonal ones.
+    pub fn neighbours8(&self, pos: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.steps(pos, 8)
+    }
+
+    fn steps(&self, (x, y): (usize, usize), n: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        STEPS[..n].iter().filter_map(move |&(dx, dy)| {
+            let pos = ((x as i64 + dx) as usize, (y as i64 + dy) as usize);
+            self.get(pos).map(|_| pos)
+        })
+    }
+
+    /// Every position, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// The first position, row by row, whose tile matches.
+    pub fn position<P>(&self, mut pred: P) -> Option<(usize, usize)>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        self.iter().find(|(_, t)| pred(t)).map(|(pos, _)| pos)
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(move |y| self.row(y))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        self.cells[x.min(self.cells.len())..]
+            .iter()
+            .step_by(self.width.max(1))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(move |x| self.column(x))
+    }
+
+    pub fn render<F>(&self, f: F) -> String
+    where
+        F: Fn(&T) -> char,
+    {
+        self.rows()
+            .map(|row| row.iter().map(&f).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: (usize, usize)) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", pos))
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, pos: (usize, usize)) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", pos))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for tile in row {
+                write!(f, "{}", tile)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A grid over every `Point`, which grows to fit whatever is stored in it. Cells that were
+/// never set hold the background.
+#[derive(Debug, Clone)]
+pub struct SparseGrid<T> {
+    origin: Point,
+    grid: Grid<T>,
+    background: T,
+}
+
+impl<T: Clone> SparseGrid<T> {
+    pub fn new(background: T) -> SparseGrid<T> {
+        SparseGrid {
+            origin: (0, 0),
+            grid: Grid::new(0, 0, background.clone()),
+            background,
+        }
+    }
+
+    pub fn get(&self, p: Point) -> &T {
+        self.index(p)
+            .and_then(|pos| self.grid.get(pos))
+            .unwrap_or(&self.background)
+    }
+
+    /// The cell at `p`, growing the grid to include it.
+    pub fn get_mut(&mut self, p: Point) -> &mut T {
+        self.grow(p);
+        let pos = self.index(p).unwrap();
+        &mut self.grid[pos]
+    }
+
+    pub fn set(&mut self, p: Point, value: T) {
+        *self.get_mut(p) = value;
+    }
+
+    /// The top-left corner and the size of the area stored so far.
+    pub fn bounds(&self) -> (Point, (usize, usize)) {
+        (self.origin, (self.grid.width, self.grid.height))
+    }
+
+    /// The stored area as a dense grid, whose `(0, 0)` is the top-left corner of `bounds`.
+    pub fn grid(&self) -> &Grid<T> {
+        &self.grid
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        let origin = self.origin;
+        self.grid
+            .iter()
+            .map(move |((x, y), t)| ((origin.0 + x as i64, origin.1 + y as i64), t))
+    }
+
+    pub fn neighbours(&self, p: Point) -> impl Iterator<Item = Point> {
+        STEPS[..4].iter().map(move |&(dx, dy)| (p.0 + dx, p.1 + dy))
+    }
+
+    pub fn neighbours8(&self, p: Point) -> impl Iterator<Item = Point> {
+        STEPS.iter().map(move |&(dx, dy)| (p.0 + dx, p.1 + dy))
+    }
+
+    fn index(&self, (x, y): Point) -> Option<(usize, usize)> {
+        let (dx, dy) = (x - self.origin.0, y - self.origin.1);
+
+        if dx >= 0 && dy >= 0 && (dx as usize) < self.grid.width && (dy as usize) < self.grid.height
+        {
+            Some((dx as usize, dy as usize))
+        } else {
+            None
+        }
+    }
+
+    fn grow(&mut self, (x, y): Point) {
+        if self.index((x, y)).is_some() {
+            return;
+        }
+
+        let (w, h) = (self.grid.width as i64, self.grid.height as i64);
+        let ((x0, y0), (x1, y1)) = if self.grid.cells.is_empty() {
+            ((x, y), (x + 1, y + 1))
+        } else {
+            (
+                (self.origin.0.min(x), self.origin.1.min(y)),
+                (
+                    (self.origin.0 + w).max(x + 1),
+                    (self.origin.1 + h).max(y + 1),
+                ),
+            )
+        };
+
+        let mut grid = Grid::new(
+            (x1 - x0) as usize,
+            (y1 - y0) as usize,
+            self.background.clone(),
+        );
+        let (dx, dy) = ((self.origin.0 - x0) as usize, (self.origin.1 - y0) as usize);
+
+        for (y, row) in self.grid.rows().enumerate() {
+            let start = (y + dy) * grid.width + dx;
+            grid.cells[start..start + row.len()].clone_from_slice(row);
+        }
+
+        self.origin = (x0, y0);
+        self.grid = grid;
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for SparseGrid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.grid.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_walks() {
+        let grid = Grid::parse("#.#\n..#", |c| match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        })
+        .unwrap();
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get((2, 1)), Some(&true));
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(
+            grid.neighbours((0, 0)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0)]
+        );
+        assert_eq!(grid.neighbours8((0, 1)).count(), 3);
+        assert_eq!(grid.column(2).collect::<Vec<_>>(), vec![&true, &true]);
+        assert_eq!(
+            grid.columns().filter_map(|mut c| c.find(|&&t| t)).count(),
+            2
+        );
+        assert_eq!(grid.rows().nth(1), Some(&[false, false, true][..]));
+        assert_eq!(grid.position(|&t| !t), Some((1, 0)));
+        assert_eq!(grid.render(|&t| if t { 'x' } else { ' ' }), "x x\n  x");
+
+        let err = |s| {
+            Grid::parse(s, |c| Some(c).filter(|&c| c != '?'))
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(err("ab\nc"), "2:1: expected 2 columns, found 1");
+        assert_eq!(err("ab\nc?"), "2:2: invalid tile `?`");
+
+        let ragged = Grid::parse_ragged("  A\n.", ' ', Some).unwrap();
+        assert_eq!(ragged.to_string(), "  A\n.  ");
+    }
+
+    #[test]
+    fn sparse_grid_grows() {
+        let mut grid = SparseGrid::new('.');
+
+        grid.set((1, 1), 'a');
+        assert_eq!(grid.bounds(), ((1, 1), (1, 1)));
+
+        grid.set((-1, 2), 'b');
+        *grid.get_mut((2, -1)) = 'c';
+        assert_eq!(grid.bounds(), ((-1, -1), (4, 4)));
+        assert_eq!(grid.get((1, 1)), &'a');
+        assert_eq!(grid.get((9, 9)), &'.');
+        assert_eq!(grid.to_string(), "...c\n....\n..a.\nb...");
+        assert_eq!(grid.iter().filter(|&(_, &c)| c != '.').count(), 3);
+        assert_eq!(grid.neighbours((0, 0)).nth(2), Some((-1, 0)));
+    }
+}
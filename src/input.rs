@@ -0,0 +1,134 @@
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Names a directory that is searched for `<day>.txt` before the default ones.
+pub const DIR_VAR: &str = "AOC19_INPUT_DIR";
+
+#[derive(Debug)]
+pub enum InputError {
+    /// None of the searched directories has an input for the day.
+    Missing { day: u32, searched: Vec<PathBuf> },
+    /// The input exists but can't be read; the path of stdin is `-`.
+    Unreadable { path: PathBuf, error: io::Error },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Missing { day, searched } => write!(
+                f,
+                "no input for day {} in {}",
+                day,
+                searched
+                    .iter()
+                    .map(|dir| dir.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            InputError::Unreadable { path, error } => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+impl Error for InputError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InputError::Missing { .. } => None,
+            InputError::Unreadable { error, .. } => Some(error),
+        }
+    }
+}
+
+/// The directories searched for inputs, in order: the one named by `AOC19_INPUT_DIR`,
+/// `res/` in the working directory and the crate's own `res/`.
+pub fn dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if let Some(dir) = env::var_os(DIR_VAR) {
+        dirs.push(PathBuf::from(dir));
+    }
+    dirs.push(PathBuf::from("res"));
+    dirs.push(Path::new(env!("CARGO_MANIFEST_DIR")).join("res"));
+
+    dirs
+}
+
+pub fn find(day: u32) -> Result<PathBuf, InputError> {
+    find_in(day, &dirs())
+}
+
+fn find_in(day: u32, dirs: &[PathBuf]) -> Result<PathBuf, InputError> {
+    dirs.iter()
+        .map(|dir| dir.join(format!("{}.txt", day)))
+        .find(|path| path.is_file())
+        .ok_or_else(|| InputError::Missing {
+            day,
+            searched: dirs.to_vec(),
+        })
+}
+
+/// Finds and reads the input of `day`.
+pub fn load(day: u32) -> Result<String, InputError> {
+    read(&find(day)?)
+}
+
+pub fn read(path: &Path) -> Result<String, InputError> {
+    fs::read_to_string(path)
+        .map(|text| normalize(&text))
+        .map_err(|error| InputError::Unreadable {
+            path: path.to_path_buf(),
+            error,
+        })
+}
+
+pub fn stdin() -> Result<String, InputError> {
+    let mut text = String::new();
+
+    io::stdin()
+        .read_to_string(&mut text)
+        .map(|_| normalize(&text))
+        .map_err(|error| InputError::Unreadable {
+            path: PathBuf::from("-"),
+            error,
+        })
+}
+
+/// Converts line endings to `\n` and drops whitespace at the end of the input. Whitespace
+/// at the start and end of each line is kept, since the mazes of days 18 and 20 rely on it.
+pub fn normalize(text: &str) -> String {
+    text.replace("\r\n", "\n").trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_endings() {
+        assert_eq!(normalize("123\r\n456\r\n\r\n"), "123\n456");
+        assert_eq!(normalize("  #.# \n  A  \n\n"), "  #.# \n  A");
+        assert_eq!(normalize("5973847\n"), "5973847");
+    }
+
+    #[test]
+    fn finds_inputs() {
+        let res = Path::new(env!("CARGO_MANIFEST_DIR")).join("res");
+        let dirs = vec![PathBuf::from("does-not-exist"), res.clone()];
+
+        assert_eq!(find_in(16, &dirs).unwrap(), res.join("16.txt"));
+        assert!(load(16).unwrap().chars().all(|c| c.is_ascii_digit()));
+
+        let err = find_in(25, &dirs).unwrap_err();
+        assert!(matches!(err, InputError::Missing { day: 25, .. }));
+        assert!(err
+            .to_string()
+            .starts_with("no input for day 25 in does-not-exist, "));
+
+        let err = read(&res.join("25.txt")).unwrap_err();
+        assert!(matches!(err, InputError::Unreadable { .. }));
+    }
+}
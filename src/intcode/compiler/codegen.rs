@@ -0,0 +1,509 @@
+use super::parser::{BinOp, Expr, Function, Stmt, UnOp};
+use super::{CompileError, Pos};
+use crate::intcode::isa::{Instruction, Mode, Opcode};
+use crate::intcode::Word;
+
+use std::collections::HashMap;
+
+/// An operand value that may only be known once the whole program has been laid out.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Value {
+    Const(Word),
+    Label(usize),
+    /// The frame size of the enclosing function plus an offset, optionally negated.
+    Frame(Word, bool),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Operand {
+    mode: Mode,
+    value: Value,
+    temp: bool,
+}
+
+impl Operand {
+    fn imm(w: Word) -> Operand {
+        Operand {
+            mode: Mode::Immediate,
+            value: Value::Const(w),
+            temp: false,
+        }
+    }
+
+    fn slot(slot: Word) -> Operand {
+        Operand {
+            mode: Mode::Relative,
+            value: Value::Const(slot),
+            temp: false,
+        }
+    }
+
+    fn label(label: usize) -> Operand {
+        Operand {
+            mode: Mode::Immediate,
+            value: Value::Label(label),
+            temp: false,
+        }
+    }
+
+    fn global(label: usize) -> Operand {
+        Operand {
+            mode: Mode::Position,
+            value: Value::Label(label),
+            temp: false,
+        }
+    }
+
+    fn frame(mode: Mode, offset: Word, negate: bool) -> Operand {
+        Operand {
+            mode,
+            value: Value::Frame(offset, negate),
+            temp: false,
+        }
+    }
+
+    fn constant(&self) -> Option<Word> {
+        match (self.mode, self.value) {
+            (Mode::Immediate, Value::Const(w)) => Some(w),
+            _ => None,
+        }
+    }
+}
+
+struct Codegen<'a> {
+    functions: &'a [Function],
+    code: Vec<Word>,
+    labels: Vec<Option<usize>>,
+    fixups: Vec<(usize, Value, usize)>,
+    frames: Vec<Word>,
+    entries: Vec<usize>,
+    retval: usize,
+    func: usize,
+    scopes: Vec<HashMap<String, Word>>,
+    next: Word,
+    max: Word,
+}
+
+// Calling convention: `rb` points at the callee's frame, which holds the return address at
+// offset 0 followed by the parameters, the locals and the temporaries. The caller stores the
+// arguments right past its own frame, moves `rb` there and jumps; the callee leaves its result
+// in a global cell and jumps back through `[rb+0]`, after which the caller restores `rb`.
+pub fn generate(functions: &[Function]) -> Result<Vec<Word>, CompileError> {
+    let mut names = HashMap::new();
+    for (i, f) in functions.iter().enumerate() {
+        if names.insert(f.name.as_str(), i).is_some() {
+            return Err(f
+                .pos
+                .error(&format!("function `{}` already defined", f.name)));
+        }
+    }
+
+    let main = match names.get("main") {
+        Some(&main) if functions[main].params.is_empty() => main,
+        Some(&main) => return Err(functions[main].pos.error("`main` must not take parameters")),
+        None => return Err(Pos { line: 1, col: 1 }.error("missing `fn main()`")),
+    };
+
+    let mut cg = Codegen {
+        functions,
+        code: Vec::new(),
+        labels: Vec::new(),
+        fixups: Vec::new(),
+        frames: vec![0; functions.len()],
+        entries: Vec::new(),
+        retval: 0,
+        func: 0,
+        scopes: Vec::new(),
+        next: 0,
+        max: 0,
+    };
+
+    cg.entries = (0..functions.len()).map(|_| cg.new_label()).collect();
+    cg.retval = cg.new_label();
+    let stack = cg.new_label();
+    let ret = cg.new_label();
+
+    cg.emit(Opcode::AdjustBase, &[Operand::label(stack)]);
+    cg.emit(
+        Opcode::Add,
+        &[Operand::label(ret), Operand::imm(0), Operand::slot(0)],
+    );
+    cg.emit(
+        Opcode::JumpIfTrue,
+        &[Operand::imm(1), Operand::label(cg.entries[main])],
+    );
+    cg.bind(ret);
+    cg.emit(Opcode::Halt, &[]);
+
+    for (i, f) in functions.iter().enumerate() {
+        cg.function(i, f)?;
+    }
+
+    cg.bind(cg.retval);
+    cg.code.push(0);
+    cg.bind(stack);
+
+    for &(at, value, func) in cg.fixups.iter() {
+        cg.code[at] = match value {
+            Value::Const(w) => w,
+            Value::Label(l) => cg.labels[l].unwrap() as Word,
+            Value::Frame(offset, false) => cg.frames[func] + offset,
+            Value::Frame(offset, true) => -(cg.frames[func] + offset),
+        };
+    }
+
+    Ok(cg.code)
+}
+
+impl<'a> Codegen<'a> {
+    fn new_label(&mut self) -> usize {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    fn bind(&mut self, label: usize) {
+        self.labels[label] = Some(self.code.len());
+    }
+
+    fn emit(&mut self, opcode: Opcode, operands: &[Operand]) {
+        let mut modes = [Mode::Position; 3];
+        for (mode, op) in modes.iter_mut().zip(operands) {
+            *mode = op.mode;
+        }
+
+        self.code.push(Instruction { opcode, modes }.encode());
+
+        for op in operands {
+            match op.value {
+                Value::Const(w) => self.code.push(w),
+                value => {
+                    self.fixups.push((self.code.len(), value, self.func));
+                    self.code.push(0);
+                }
+            }
+        }
+    }
+
+    fn alloc(&mut self) -> Operand {
+        let slot = self.next;
+        self.next += 1;
+        self.max = self.max.max(self.next);
+
+        Operand {
+            temp: true,
+            ..Operand::slot(slot)
+        }
+    }
+
+    fn free(&mut self, op: Operand) {
+        if op.temp {
+            self.next -= 1;
+            debug_assert_eq!(op.value, Value::Const(self.next));
+        }
+    }
+
+    fn mov(&mut self, src: Operand, dst: Operand) {
+        if (src.mode, src.value) != (dst.mode, dst.value) {
+            self.emit(Opcode::Add, &[src, Operand::imm(0), dst]);
+        }
+    }
+
+    fn lookup(&self, name: &str, pos: Pos) -> Result<Operand, CompileError> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .map(|&slot| Operand::slot(slot))
+            .ok_or_else(|| pos.error(&format!("undefined variable `{}`", name)))
+    }
+
+    fn function(&mut self, idx: usize, f: &Function) -> Result<(), CompileError> {
+        self.func = idx;
+        self.bind(self.entries[idx]);
+
+        let mut params = HashMap::new();
+        for (i, p) in f.params.iter().enumerate() {
+            if params.insert(p.clone(), i as Word + 1).is_some() {
+                return Err(f.pos.error(&format!("duplicate parameter `{}`", p)));
+            }
+        }
+
+        self.scopes = vec![params];
+        self.next = f.params.len() as Word + 1;
+        self.max = self.next;
+
+        self.block(&f.body)?;
+        self.ret(Operand::imm(0));
+
+        self.frames[idx] = self.max;
+        Ok(())
+    }
+
+    fn block(&mut self, stmts: &[Stmt]) -> Result<(), CompileError> {
+        let next = self.next;
+        self.scopes.push(HashMap::new());
+
+        for stmt in stmts {
+            self.stmt(stmt)?;
+        }
+
+        self.scopes.pop();
+        self.next = next;
+        Ok(())
+    }
+
+    fn ret(&mut self, v: Operand) {
+        self.mov(v, Operand::global(self.retval));
+        self.emit(Opcode::JumpIfFalse, &[Operand::imm(0), Operand::slot(0)]);
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Result<(), CompileError> {
+        match stmt {
+            Stmt::Let(name, e, pos) => {
+                if self.scopes.last().unwrap().contains_key(name) {
+                    return Err(pos.error(&format!("variable `{}` already declared", name)));
+                }
+
+                let v = self.expr(e)?;
+                self.free(v);
+
+                let slot = self.next;
+                self.next += 1;
+                self.max = self.max.max(self.next);
+
+                self.mov(v, Operand::slot(slot));
+                self.scopes.last_mut().unwrap().insert(name.clone(), slot);
+            }
+            Stmt::Assign(name, e, pos) => {
+                let dst = self.lookup(name, *pos)?;
+                let v = self.expr(e)?;
+                self.mov(v, dst);
+                self.free(v);
+            }
+            Stmt::If(cond, then, otherwise) => {
+                let (other, end) = (self.new_label(), self.new_label());
+
+                let c = self.expr(cond)?;
+                self.free(c);
+                self.emit(Opcode::JumpIfFalse, &[c, Operand::label(other)]);
+
+                self.block(then)?;
+                if !otherwise.is_empty() {
+                    self.emit(Opcode::JumpIfTrue, &[Operand::imm(1), Operand::label(end)]);
+                }
+
+                self.bind(other);
+                self.block(otherwise)?;
+                self.bind(end);
+            }
+            Stmt::While(cond, body) => {
+                let (top, end) = (self.new_label(), self.new_label());
+
+                self.bind(top);
+                let c = self.expr(cond)?;
+                self.free(c);
+                self.emit(Opcode::JumpIfFalse, &[c, Operand::label(end)]);
+
+                self.block(body)?;
+                self.emit(Opcode::JumpIfTrue, &[Operand::imm(1), Operand::label(top)]);
+                self.bind(end);
+            }
+            Stmt::Return(e) => {
+                let v = match e {
+                    Some(e) => self.expr(e)?,
+                    None => Operand::imm(0),
+                };
+                self.free(v);
+                self.ret(v);
+            }
+            Stmt::Expr(e) => {
+                let v = self.expr(e)?;
+                self.free(v);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn expr(&mut self, e: &Expr) -> Result<Operand, CompileError> {
+        Ok(match e {
+            Expr::Num(n) => Operand::imm(*n),
+            Expr::Var(name, pos) => self.lookup(name, *pos)?,
+            Expr::Unary(op, e) => {
+                let v = self.expr(e)?;
+
+                if let Some(w) = v.constant() {
+                    return Ok(Operand::imm(match op {
+                        UnOp::Neg => -w,
+                        UnOp::Not => (w == 0).into(),
+                    }));
+                }
+
+                self.free(v);
+                let t = self.alloc();
+                match op {
+                    UnOp::Neg => self.emit(Opcode::Mul, &[v, Operand::imm(-1), t]),
+                    UnOp::Not => self.emit(Opcode::Equals, &[v, Operand::imm(0), t]),
+                }
+                t
+            }
+            Expr::Binary(op @ BinOp::And, a, b) | Expr::Binary(op @ BinOp::Or, a, b) => {
+                // t = !a; if it already decides the result, skip b, otherwise t = !b; then t = !t
+                let (t, skip) = (self.alloc(), self.new_label());
+                let jump = if *op == BinOp::And {
+                    Opcode::JumpIfTrue
+                } else {
+                    Opcode::JumpIfFalse
+                };
+
+                let v = self.expr(a)?;
+                self.emit(Opcode::Equals, &[v, Operand::imm(0), t]);
+                self.free(v);
+                self.emit(jump, &[t, Operand::label(skip)]);
+
+                let v = self.expr(b)?;
+                self.emit(Opcode::Equals, &[v, Operand::imm(0), t]);
+                self.free(v);
+
+                self.bind(skip);
+                self.emit(Opcode::Equals, &[t, Operand::imm(0), t]);
+                t
+            }
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (self.expr(a)?, self.expr(b)?);
+
+                if let (Some(x), Some(y)) = (a.constant(), b.constant()) {
+                    return Ok(Operand::imm(fold(*op, x, y)));
+                }
+
+                self.free(b);
+                self.free(a);
+                let t = self.alloc();
+
+                match op {
+                    BinOp::Add => self.emit(Opcode::Add, &[a, b, t]),
+                    BinOp::Mul => self.emit(Opcode::Mul, &[a, b, t]),
+                    BinOp::Sub => match b.constant() {
+                        Some(y) => self.emit(Opcode::Add, &[a, Operand::imm(-y), t]),
+                        None => {
+                            let s = self.alloc();
+                            self.emit(Opcode::Mul, &[b, Operand::imm(-1), s]);
+                            self.emit(Opcode::Add, &[a, s, t]);
+                            self.free(s);
+                        }
+                    },
+                    BinOp::Lt => self.emit(Opcode::LessThan, &[a, b, t]),
+                    BinOp::Gt => self.emit(Opcode::LessThan, &[b, a, t]),
+                    BinOp::Le | BinOp::Ge => {
+                        let (a, b) = if *op == BinOp::Le { (b, a) } else { (a, b) };
+                        self.emit(Opcode::LessThan, &[a, b, t]);
+                        self.emit(Opcode::Equals, &[t, Operand::imm(0), t]);
+                    }
+                    BinOp::Eq => self.emit(Opcode::Equals, &[a, b, t]),
+                    BinOp::Ne => {
+                        self.emit(Opcode::Equals, &[a, b, t]);
+                        self.emit(Opcode::Equals, &[t, Operand::imm(0), t]);
+                    }
+                    BinOp::And | BinOp::Or => unreachable!(),
+                }
+                t
+            }
+            Expr::Call(name, args, pos) => self.call(name, args, *pos)?,
+        })
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr], pos: Pos) -> Result<Operand, CompileError> {
+        let arity = |n: usize| {
+            if args.len() == n {
+                Ok(())
+            } else {
+                Err(pos.error(&format!(
+                    "`{}` takes {} argument(s), {} given",
+                    name,
+                    n,
+                    args.len()
+                )))
+            }
+        };
+
+        match name {
+            "input" => {
+                arity(0)?;
+                let t = self.alloc();
+                self.emit(Opcode::Input, &[t]);
+                return Ok(t);
+            }
+            "output" => {
+                arity(1)?;
+                let v = self.expr(&args[0])?;
+                self.emit(Opcode::Output, &[v]);
+                self.free(v);
+                return Ok(Operand::imm(0));
+            }
+            _ => (),
+        }
+
+        let callee = self
+            .functions
+            .iter()
+            .position(|f| f.name == name)
+            .ok_or_else(|| pos.error(&format!("undefined function `{}`", name)))?;
+        arity(self.functions[callee].params.len())?;
+
+        let vals = args
+            .iter()
+            .map(|arg| self.expr(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (i, &v) in vals.iter().enumerate() {
+            self.mov(v, Operand::frame(Mode::Relative, i as Word + 1, false));
+        }
+        for &v in vals.iter().rev() {
+            self.free(v);
+        }
+
+        let ret = self.new_label();
+
+        self.emit(
+            Opcode::Add,
+            &[
+                Operand::label(ret),
+                Operand::imm(0),
+                Operand::frame(Mode::Relative, 0, false),
+            ],
+        );
+        self.emit(
+            Opcode::AdjustBase,
+            &[Operand::frame(Mode::Immediate, 0, false)],
+        );
+        self.emit(
+            Opcode::JumpIfTrue,
+            &[Operand::imm(1), Operand::label(self.entries[callee])],
+        );
+        self.bind(ret);
+        self.emit(
+            Opcode::AdjustBase,
+            &[Operand::frame(Mode::Immediate, 0, true)],
+        );
+
+        let t = self.alloc();
+        self.mov(Operand::global(self.retval), t);
+        Ok(t)
+    }
+}
+
+fn fold(op: BinOp, x: Word, y: Word) -> Word {
+    match op {
+        BinOp::Add => x + y,
+        BinOp::Sub => x - y,
+        BinOp::Mul => x * y,
+        BinOp::Lt => (x < y).into(),
+        BinOp::Le => (x <= y).into(),
+        BinOp::Gt => (x > y).into(),
+        BinOp::Ge => (x >= y).into(),
+        BinOp::Eq => (x == y).into(),
+        BinOp::Ne => (x != y).into(),
+        BinOp::And => (x != 0 && y != 0).into(),
+        BinOp::Or => (x != 0 || y != 0).into(),
+    }
+}
@@ -0,0 +1,157 @@
+use super::{CompileError, Pos};
+use crate::intcode::Word;
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tok {
+    Num(Word),
+    Ident(String),
+    Fn,
+    Let,
+    If,
+    Else,
+    While,
+    Return,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Comma,
+    Semi,
+    Assign,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Plus,
+    Minus,
+    Star,
+    Not,
+    And,
+    Or,
+    Eof,
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Tok::Num(n) => return write!(f, "{}", n),
+            Tok::Ident(name) => return write!(f, "`{}`", name),
+            Tok::Eof => return write!(f, "end of input"),
+            Tok::Fn => "fn",
+            Tok::Let => "let",
+            Tok::If => "if",
+            Tok::Else => "else",
+            Tok::While => "while",
+            Tok::Return => "return",
+            Tok::LParen => "(",
+            Tok::RParen => ")",
+            Tok::LBrace => "{",
+            Tok::RBrace => "}",
+            Tok::Comma => ",",
+            Tok::Semi => ";",
+            Tok::Assign => "=",
+            Tok::Eq => "==",
+            Tok::Ne => "!=",
+            Tok::Lt => "<",
+            Tok::Le => "<=",
+            Tok::Gt => ">",
+            Tok::Ge => ">=",
+            Tok::Plus => "+",
+            Tok::Minus => "-",
+            Tok::Star => "*",
+            Tok::Not => "!",
+            Tok::And => "&&",
+            Tok::Or => "||",
+        };
+        write!(f, "'{}'", s)
+    }
+}
+
+pub fn tokenize(src: &str) -> Result<Vec<(Tok, Pos)>, CompileError> {
+    let chars = src.chars().collect::<Vec<_>>();
+    let mut toks = Vec::new();
+    let (mut i, mut line, mut col) = (0, 1, 1);
+
+    while i < chars.len() {
+        let c = chars[i];
+        let pos = Pos { line, col };
+        let next = chars.get(i + 1).cloned();
+
+        if c == '\n' {
+            i += 1;
+            line += 1;
+            col = 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            col += 1;
+            continue;
+        }
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        let start = i;
+
+        let tok = if c.is_ascii_digit() {
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let s = chars[start..i].iter().collect::<String>();
+            Tok::Num(Word::from_str(&s).map_err(|_| pos.error("number too large"))?)
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            match chars[start..i].iter().collect::<String>().as_str() {
+                "fn" => Tok::Fn,
+                "let" => Tok::Let,
+                "if" => Tok::If,
+                "else" => Tok::Else,
+                "while" => Tok::While,
+                "return" => Tok::Return,
+                s => Tok::Ident(s.to_string()),
+            }
+        } else {
+            let (tok, len) = match (c, next) {
+                ('=', Some('=')) => (Tok::Eq, 2),
+                ('!', Some('=')) => (Tok::Ne, 2),
+                ('<', Some('=')) => (Tok::Le, 2),
+                ('>', Some('=')) => (Tok::Ge, 2),
+                ('&', Some('&')) => (Tok::And, 2),
+                ('|', Some('|')) => (Tok::Or, 2),
+                ('(', _) => (Tok::LParen, 1),
+                (')', _) => (Tok::RParen, 1),
+                ('{', _) => (Tok::LBrace, 1),
+                ('}', _) => (Tok::RBrace, 1),
+                (',', _) => (Tok::Comma, 1),
+                (';', _) => (Tok::Semi, 1),
+                ('=', _) => (Tok::Assign, 1),
+                ('<', _) => (Tok::Lt, 1),
+                ('>', _) => (Tok::Gt, 1),
+                ('+', _) => (Tok::Plus, 1),
+                ('-', _) => (Tok::Minus, 1),
+                ('*', _) => (Tok::Star, 1),
+                ('!', _) => (Tok::Not, 1),
+                _ => return Err(pos.error(&format!("unexpected character '{}'", c))),
+            };
+            i += len;
+            tok
+        };
+
+        col += i - start;
+        toks.push((tok, pos));
+    }
+
+    toks.push((Tok::Eof, Pos { line, col }));
+    Ok(toks)
+}
@@ -0,0 +1,145 @@
+mod codegen;
+mod lexer;
+mod parser;
+
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Pos {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Pos {
+    fn error(&self, msg: &str) -> CompileError {
+        CompileError {
+            line: self.line,
+            col: self.col,
+            msg: msg.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileError {
+    pub line: usize,
+    pub col: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.msg)
+    }
+}
+
+/// Compiles a program into the comma separated form accepted by `Intcode::new`.
+///
+/// A program is a list of `fn name(params) { ... }` items, one of which must be `main()`.
+/// Statements are `let x = e;`, `x = e;`, `if`/`else`, `while`, `return e;` and bare
+/// expressions; `input()` and `output(e)` are built in.
+pub fn compile(src: &str) -> Result<String, CompileError> {
+    let functions = parser::parse(lexer::tokenize(src)?)?;
+    let code = codegen::generate(&functions)?;
+
+    Ok(code
+        .iter()
+        .map(|w| w.to_string())
+        .collect::<Vec<_>>()
+        .join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::{Intcode, StopCondition, Word};
+
+    fn run(src: &str, inputs: &[Word]) -> Vec<Word> {
+        let program = compile(src).unwrap();
+        let (outs, sc) = Intcode::new(&program).inputs(inputs).run();
+
+        assert_eq!(sc, StopCondition::Halt);
+        outs
+    }
+
+    #[test]
+    fn recursion() {
+        let src = "
+            fn fib(n) {
+                if n < 2 {
+                    return n;
+                }
+                return fib(n - 1) + fib(n - 2);
+            }
+
+            fn main() {
+                let i = 0;
+                while i <= 10 {
+                    output(fib(i));
+                    i = i + 1;
+                }
+            }
+        ";
+
+        assert_eq!(run(src, &[]), vec![0, 1, 1, 2, 3, 5, 8, 13, 21, 34, 55]);
+    }
+
+    #[test]
+    fn loops_and_operators() {
+        let src = "
+            // echoes inputs until a zero, classifying each one
+            fn main() {
+                let x = input();
+                while x != 0 {
+                    if x > 0 && !(x >= 100) {
+                        output(1);
+                    } else if x < 0 || x == 100 {
+                        output(-x * 2 - 1);
+                    } else {
+                        output(0);
+                    }
+                    x = input();
+                }
+            }
+        ";
+
+        assert_eq!(run(src, &[5, -3, 100, 200, 0]), vec![1, 5, -201, 0]);
+    }
+
+    #[test]
+    fn nested_calls() {
+        let src = "
+            fn add(a, b) { return a + b; }
+            fn mul3(a, b, c) { return a * b * c; }
+            fn main() {
+                let x = input();
+                output(add(mul3(x, add(1, x), 2), add(x, add(x, x))));
+            }
+        ";
+
+        assert_eq!(run(src, &[4]), vec![4 * 5 * 2 + 12]);
+    }
+
+    #[test]
+    fn errors_have_positions() {
+        let err = |src| compile(src).unwrap_err().to_string();
+
+        assert_eq!(
+            err("fn main() {\n  output(y);\n}"),
+            "2:10: undefined variable `y`"
+        );
+        assert_eq!(
+            err("fn main() {\n  let x = 1 $ 2;\n}"),
+            "2:13: unexpected character '$'"
+        );
+        assert_eq!(
+            err("fn f(a) {}\nfn main() { f(); }"),
+            "2:13: `f` takes 1 argument(s), 0 given"
+        );
+        assert_eq!(err("fn f() {}"), "1:1: missing `fn main()`");
+        assert_eq!(
+            err("fn main() { let x = 1 }"),
+            "1:23: expected ';', found '}'"
+        );
+    }
+}
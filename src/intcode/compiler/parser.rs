@@ -0,0 +1,304 @@
+use super::lexer::Tok;
+use super::{CompileError, Pos};
+use crate::intcode::Word;
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Vec<Stmt>,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone)]
+pub enum Stmt {
+    Let(String, Expr, Pos),
+    Assign(String, Expr, Pos),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    Return(Option<Expr>),
+    Expr(Expr),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UnOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Num(Word),
+    Var(String, Pos),
+    Unary(UnOp, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>, Pos),
+}
+
+struct Parser {
+    toks: Vec<(Tok, Pos)>,
+    idx: usize,
+}
+
+pub fn parse(toks: Vec<(Tok, Pos)>) -> Result<Vec<Function>, CompileError> {
+    let mut p = Parser { toks, idx: 0 };
+    let mut functions = Vec::new();
+
+    while p.peek() != &Tok::Eof {
+        functions.push(p.function()?);
+    }
+
+    Ok(functions)
+}
+
+impl Parser {
+    fn peek(&self) -> &Tok {
+        &self.toks[self.idx].0
+    }
+
+    fn pos(&self) -> Pos {
+        self.toks[self.idx].1
+    }
+
+    fn bump(&mut self) -> Tok {
+        let tok = self.toks[self.idx].0.clone();
+        if tok != Tok::Eof {
+            self.idx += 1;
+        }
+        tok
+    }
+
+    fn eat(&mut self, tok: Tok) -> bool {
+        if *self.peek() == tok {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, tok: Tok, what: &str) -> Result<(), CompileError> {
+        if self.eat(tok) {
+            Ok(())
+        } else {
+            Err(self.unexpected(what))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, CompileError> {
+        match self.peek().clone() {
+            Tok::Ident(name) => {
+                self.bump();
+                Ok(name)
+            }
+            _ => Err(self.unexpected("identifier")),
+        }
+    }
+
+    fn unexpected(&self, what: &str) -> CompileError {
+        self.pos()
+            .error(&format!("expected {}, found {}", what, self.peek()))
+    }
+
+    fn function(&mut self) -> Result<Function, CompileError> {
+        let pos = self.pos();
+
+        self.expect(Tok::Fn, "'fn'")?;
+        let name = self.ident()?;
+        self.expect(Tok::LParen, "'('")?;
+
+        let mut params = Vec::new();
+        if !self.eat(Tok::RParen) {
+            loop {
+                params.push(self.ident()?);
+                if self.eat(Tok::RParen) {
+                    break;
+                }
+                self.expect(Tok::Comma, "',' or ')'")?;
+            }
+        }
+
+        let body = self.block()?;
+
+        Ok(Function {
+            name,
+            params,
+            body,
+            pos,
+        })
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, CompileError> {
+        self.expect(Tok::LBrace, "'{'")?;
+
+        let mut stmts = Vec::new();
+        while !self.eat(Tok::RBrace) {
+            stmts.push(self.stmt()?);
+        }
+
+        Ok(stmts)
+    }
+
+    fn stmt(&mut self) -> Result<Stmt, CompileError> {
+        let pos = self.pos();
+
+        let stmt = match self.peek().clone() {
+            Tok::Let => {
+                self.bump();
+                let name = self.ident()?;
+                self.expect(Tok::Assign, "'='")?;
+                Stmt::Let(name, self.expr()?, pos)
+            }
+            Tok::If => {
+                self.bump();
+                return self.if_stmt();
+            }
+            Tok::While => {
+                self.bump();
+                let cond = self.expr()?;
+                return Ok(Stmt::While(cond, self.block()?));
+            }
+            Tok::Return => {
+                self.bump();
+                if *self.peek() == Tok::Semi {
+                    Stmt::Return(None)
+                } else {
+                    Stmt::Return(Some(self.expr()?))
+                }
+            }
+            Tok::Ident(name) if self.toks[self.idx + 1].0 == Tok::Assign => {
+                self.bump();
+                self.bump();
+                Stmt::Assign(name, self.expr()?, pos)
+            }
+            _ => Stmt::Expr(self.expr()?),
+        };
+
+        self.expect(Tok::Semi, "';'")?;
+        Ok(stmt)
+    }
+
+    fn if_stmt(&mut self) -> Result<Stmt, CompileError> {
+        let cond = self.expr()?;
+        let then = self.block()?;
+
+        let otherwise = if self.eat(Tok::Else) {
+            if self.eat(Tok::If) {
+                vec![self.if_stmt()?]
+            } else {
+                self.block()?
+            }
+        } else {
+            vec![]
+        };
+
+        Ok(Stmt::If(cond, then, otherwise))
+    }
+
+    fn expr(&mut self) -> Result<Expr, CompileError> {
+        self.binary(0)
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, CompileError> {
+        const LEVELS: [&[(Tok, BinOp)]; 5] = [
+            &[(Tok::Or, BinOp::Or)],
+            &[(Tok::And, BinOp::And)],
+            &[(Tok::Eq, BinOp::Eq), (Tok::Ne, BinOp::Ne)],
+            &[
+                (Tok::Lt, BinOp::Lt),
+                (Tok::Le, BinOp::Le),
+                (Tok::Gt, BinOp::Gt),
+                (Tok::Ge, BinOp::Ge),
+            ],
+            &[(Tok::Plus, BinOp::Add), (Tok::Minus, BinOp::Sub)],
+        ];
+
+        if level == LEVELS.len() {
+            return self.term();
+        }
+
+        let mut lhs = self.binary(level + 1)?;
+
+        while let Some(&(_, op)) = LEVELS[level].iter().find(|(t, _)| t == self.peek()) {
+            self.bump();
+            let rhs = self.binary(level + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Expr, CompileError> {
+        let mut lhs = self.unary()?;
+
+        while self.eat(Tok::Star) {
+            let rhs = self.unary()?;
+            lhs = Expr::Binary(BinOp::Mul, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, CompileError> {
+        if self.eat(Tok::Minus) {
+            Ok(Expr::Unary(UnOp::Neg, Box::new(self.unary()?)))
+        } else if self.eat(Tok::Not) {
+            Ok(Expr::Unary(UnOp::Not, Box::new(self.unary()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, CompileError> {
+        let pos = self.pos();
+
+        match self.peek().clone() {
+            Tok::Num(n) => {
+                self.bump();
+                Ok(Expr::Num(n))
+            }
+            Tok::Ident(name) => {
+                self.bump();
+
+                if !self.eat(Tok::LParen) {
+                    return Ok(Expr::Var(name, pos));
+                }
+
+                let mut args = Vec::new();
+                if !self.eat(Tok::RParen) {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.eat(Tok::RParen) {
+                            break;
+                        }
+                        self.expect(Tok::Comma, "',' or ')'")?;
+                    }
+                }
+
+                Ok(Expr::Call(name, args, pos))
+            }
+            Tok::LParen => {
+                self.bump();
+                let e = self.expr()?;
+                self.expect(Tok::RParen, "')'")?;
+                Ok(e)
+            }
+            _ => Err(self.unexpected("expression")),
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use super::disasm;
+use super::isa::Instruction;
+use super::{Intcode, StopCondition, Word};
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Debug, Clone)]
+pub struct Coverage {
+    program: Vec<Word>,
+    seen: BTreeMap<usize, Word>,
+    hits: BTreeMap<usize, u64>,
+    branches: BTreeMap<usize, (u64, u64)>,
+    runs: u64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Branch {
+    pub addr: usize,
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+impl Coverage {
+    pub fn new(program: &str) -> Coverage {
+        Coverage {
+            program: Intcode::parse(program),
+            seen: BTreeMap::new(),
+            hits: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            runs: 0,
+        }
+    }
+
+    /// Runs `ic` until it stops, recording every instruction it executes.
+    pub fn run(&mut self, ic: &mut Intcode) -> (Vec<Word>, StopCondition) {
+        let mut outs = Vec::with_capacity(16);
+
+        self.runs += 1;
+
+        loop {
+            let pc = ic.pc();
+            let word = ic.value(pc);
+            let insn = Instruction::decode(word).ok();
+
+            let sc = ic.step(&mut outs);
+
+            if let None | Some(StopCondition::Halt) = sc {
+                *self.hits.entry(pc).or_insert(0) += 1;
+                self.seen.insert(pc, word);
+            }
+
+            if let Some(insn) = insn.filter(|insn| insn.opcode.is_jump()) {
+                let branch = self.branches.entry(pc).or_insert((0, 0));
+                if ic.pc() == pc + insn.size() {
+                    branch.1 += 1;
+                } else {
+                    branch.0 += 1;
+                }
+            }
+
+            if let Some(sc) = sc {
+                return (outs, sc);
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &Coverage) {
+        assert_eq!(
+            self.program, other.program,
+            "coverage of different programs"
+        );
+
+        self.seen.extend(other.seen.iter());
+
+        for (&addr, &n) in other.hits.iter() {
+            *self.hits.entry(addr).or_insert(0) += n;
+        }
+        for (&addr, &(t, nt)) in other.branches.iter() {
+            let branch = self.branches.entry(addr).or_insert((0, 0));
+            branch.0 += t;
+            branch.1 += nt;
+        }
+        self.runs += other.runs;
+    }
+
+    pub fn runs(&self) -> u64 {
+        self.runs
+    }
+
+    pub fn hits(&self, addr: usize) -> u64 {
+        self.hits.get(&addr).cloned().unwrap_or(0)
+    }
+
+    /// Addresses of the instructions in the program that never ran.
+    pub fn missed(&self) -> Vec<usize> {
+        self.listing()
+            .code
+            .keys()
+            .filter(|addr| !self.hits.contains_key(addr))
+            .cloned()
+            .collect()
+    }
+
+    pub fn branches(&self) -> Vec<Branch> {
+        self.listing()
+            .code
+            .iter()
+            .filter(|(_, insn)| insn.opcode.is_jump())
+            .map(|(&addr, _)| {
+                let (taken, not_taken) = self.branches.get(&addr).cloned().unwrap_or((0, 0));
+                Branch {
+                    addr,
+                    taken,
+                    not_taken,
+                }
+            })
+            .collect()
+    }
+
+    /// Renders the program as a disassembly annotated with execution counts. Instructions that
+    /// never ran are marked with `#####`.
+    pub fn annotated(&self) -> String {
+        let memory = self.memory();
+        let listing = self.listing();
+        let mut out = String::new();
+        let mut addr = 0;
+
+        while addr < self.program.len() {
+            match listing.code.get(&addr) {
+                Some(insn) => {
+                    let count = match self.hits.get(&addr) {
+                        Some(n) => n.to_string(),
+                        None => "#####".to_string(),
+                    };
+
+                    write!(
+                        out,
+                        "{:>9} | {:>5}: {}",
+                        count,
+                        addr,
+                        disasm::format_instruction(&memory, addr, insn)
+                    )
+                    .unwrap();
+
+                    if insn.opcode.is_jump() {
+                        let (t, nt) = self.branches.get(&addr).cloned().unwrap_or((0, 0));
+                        write!(out, "  ; taken {}, not taken {}", t, nt).unwrap();
+                    }
+
+                    addr += insn.size();
+                }
+                None => {
+                    write!(out, "{:>9} | {:>5}: .word {}", "", addr, memory[addr]).unwrap();
+                    addr += 1;
+                }
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /// Renders a line-oriented summary:
+    ///
+    /// ```text
+    /// runs <n>
+    /// insn <addr> <hits>
+    /// branch <addr> <taken> <not taken>
+    /// total <instructions> <executed> <branches> <fully covered branches>
+    /// ```
+    pub fn summary(&self) -> String {
+        let listing = self.listing();
+        let branches = self.branches();
+        let mut out = String::new();
+
+        writeln!(out, "runs {}", self.runs).unwrap();
+
+        for addr in listing.code.keys() {
+            writeln!(out, "insn {} {}", addr, self.hits(*addr)).unwrap();
+        }
+        for b in branches.iter() {
+            writeln!(out, "branch {} {} {}", b.addr, b.taken, b.not_taken).unwrap();
+        }
+
+        writeln!(
+            out,
+            "total {} {} {} {}",
+            listing.code.len(),
+            listing.code.len() - self.missed().len(),
+            branches.len(),
+            branches
+                .iter()
+                .filter(|b| b.taken > 0 && b.not_taken > 0)
+                .count()
+        )
+        .unwrap();
+
+        out
+    }
+
+    /// The program as it was executed, with self-modified instructions patched in.
+    fn memory(&self) -> Vec<Word> {
+        let mut memory = self.program.clone();
+        for (&addr, &w) in self.seen.range(..memory.len()) {
+            memory[addr] = w;
+        }
+        memory
+    }
+
+    fn listing(&self) -> disasm::Listing {
+        let memory = self.memory();
+        let mut listing =
+            disasm::reachable(&memory, std::iter::once(0).chain(self.hits.keys().cloned()));
+        disasm::sweep(&memory, &mut listing);
+        listing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_5_diagnostics_coverage() {
+        let program = include_str!("../../res/5.txt");
+
+        let mut cov = Coverage::new(program);
+        cov.run(&mut Intcode::new(program).inputs(&[5]));
+
+        for b in cov.branches() {
+            assert_eq!(b.taken + b.not_taken, cov.hits(b.addr));
+        }
+
+        let missed = cov.missed();
+        assert!(!missed.is_empty());
+
+        cov.run(&mut Intcode::new(program).inputs(&[1]));
+        assert_eq!(cov.runs(), 2);
+        assert!(cov.missed().len() < missed.len());
+
+        let annotated = cov.annotated();
+        assert!(annotated.contains("#####"));
+        assert!(annotated.contains("; taken"));
+        assert!(cov.summary().starts_with("runs 2\n"));
+    }
+
+    #[test]
+    fn merge_accumulates() {
+        let program = include_str!("../../res/19.txt");
+        let mut total = Coverage::new(program);
+
+        for y in 0..5 {
+            let mut cov = Coverage::new(program);
+            for x in 0..5 {
+                cov.run(&mut Intcode::new(program).inputs(&[x, y]));
+            }
+            total.merge(&cov);
+        }
+
+        let mut single = Coverage::new(program);
+        for y in 0..5 {
+            for x in 0..5 {
+                single.run(&mut Intcode::new(program).inputs(&[x, y]));
+            }
+        }
+
+        assert_eq!(total.runs(), 25);
+        assert_eq!(total.summary(), single.summary());
+        assert_eq!(total.hits(0), 25);
+    }
+}
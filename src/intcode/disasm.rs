@@ -0,0 +1,162 @@
+use super::isa::{Instruction, Mode, Opcode};
+use super::Word;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone, Default)]
+pub struct Listing {
+    pub code: BTreeMap<usize, Instruction>,
+    pub indirect: BTreeSet<usize>,
+}
+
+/// Discovers the instructions reachable from `entries` by following fall-through and constant
+/// jump targets. Jumps to computed targets are recorded in `indirect`.
+pub fn reachable<I>(memory: &[Word], entries: I) -> Listing
+where
+    I: IntoIterator<Item = usize>,
+{
+    let mut listing = Listing::default();
+    let mut work = entries.into_iter().collect::<Vec<_>>();
+
+    while let Some(addr) = work.pop() {
+        if listing.code.contains_key(&addr) {
+            continue;
+        }
+
+        let insn = match decode_at(memory, addr) {
+            Some(insn) => insn,
+            None => continue,
+        };
+
+        listing.code.insert(addr, insn);
+
+        if insn.opcode.is_jump() && insn.modes[1] != Mode::Immediate {
+            listing.indirect.insert(addr);
+        }
+
+        work.extend(successors(memory, addr, &insn));
+    }
+
+    listing
+}
+
+/// Adds to `listing` every instruction that decodes in the gaps between the ones already known,
+/// so that code only reachable through computed jumps is listed too.
+pub fn sweep(memory: &[Word], listing: &mut Listing) {
+    let mut addr = 0;
+
+    while addr < memory.len() {
+        if let Some(insn) = listing.code.get(&addr) {
+            addr += insn.size();
+            continue;
+        }
+
+        match decode_at(memory, addr) {
+            Some(insn)
+                if listing
+                    .code
+                    .range(addr + 1..addr + insn.size())
+                    .next()
+                    .is_none() =>
+            {
+                listing.code.insert(addr, insn);
+                addr += insn.size();
+            }
+            _ => addr += 1,
+        }
+    }
+}
+
+/// Returns the statically known successors of the instruction at `addr`.
+pub fn successors(memory: &[Word], addr: usize, insn: &Instruction) -> Vec<usize> {
+    let next = addr + insn.size();
+
+    match insn.opcode {
+        Opcode::Halt => vec![],
+        Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+            let target = if insn.modes[1] == Mode::Immediate {
+                usize::try_from(memory[addr + 2]).ok()
+            } else {
+                None
+            };
+
+            match (insn.modes[0], target) {
+                (Mode::Immediate, Some(target)) => {
+                    if (memory[addr + 1] != 0) == (insn.opcode == Opcode::JumpIfTrue) {
+                        vec![target]
+                    } else {
+                        vec![next]
+                    }
+                }
+                (Mode::Immediate, None) => {
+                    if (memory[addr + 1] != 0) == (insn.opcode == Opcode::JumpIfTrue) {
+                        vec![]
+                    } else {
+                        vec![next]
+                    }
+                }
+                (_, Some(target)) => vec![next, target],
+                (_, None) => vec![next],
+            }
+        }
+        _ => vec![next],
+    }
+}
+
+pub fn decode_at(memory: &[Word], addr: usize) -> Option<Instruction> {
+    let insn = Instruction::decode(*memory.get(addr)?).ok()?;
+
+    if addr + insn.size() <= memory.len() {
+        Some(insn)
+    } else {
+        None
+    }
+}
+
+pub fn format_operand(mode: Mode, w: Word) -> String {
+    match mode {
+        Mode::Position => format!("[{}]", w),
+        Mode::Immediate => format!("{}", w),
+        Mode::Relative if w < 0 => format!("[rb-{}]", -w),
+        Mode::Relative => format!("[rb+{}]", w),
+    }
+}
+
+pub fn format_instruction(memory: &[Word], addr: usize, insn: &Instruction) -> String {
+    let operands = (0..insn.opcode.arity())
+        .map(|i| format_operand(insn.modes[i], memory[addr + 1 + i]))
+        .collect::<Vec<_>>();
+
+    if operands.is_empty() {
+        insn.opcode.mnemonic().to_string()
+    } else {
+        format!("{} {}", insn.opcode.mnemonic(), operands.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::Intcode;
+
+    #[test]
+    fn reachable_follows_jumps() {
+        // jump over a data word, then loop on a computed target
+        let memory = Intcode::parse("1105,1,4,42,1001,3,1,3,2106,0,10,99");
+        let listing = reachable(&memory, vec![0]);
+
+        assert_eq!(
+            listing.code.keys().cloned().collect::<Vec<_>>(),
+            vec![0, 4, 8]
+        );
+        assert_eq!(
+            listing.indirect.iter().cloned().collect::<Vec<_>>(),
+            vec![8]
+        );
+        assert_eq!(
+            format_instruction(&memory, 8, &listing.code[&8]),
+            "jz 0, [rb+10]"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use super::{Intcode, Word};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+pub type Point = (i64, i64);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Status {
+    Wall,
+    Open,
+    Special,
+}
+
+/// Everything reachable from the starting point, which is always `(0, 0)`.
+#[derive(Debug, Clone, Default)]
+pub struct World {
+    pub map: HashMap<Point, Status>,
+    pub specials: Vec<Point>,
+}
+
+/// A robot program that broke the move-and-report protocol.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExploreError {
+    /// A move produced no output.
+    NoStatus { pos: Point },
+    /// A move produced an output the decoder doesn't know.
+    UnknownStatus { pos: Point, output: Word },
+    /// Retracing a step the robot had just taken ran into a wall.
+    BlockedBacktrack { pos: Point },
+}
+
+impl fmt::Display for ExploreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ExploreError::NoStatus { pos } => {
+                write!(f, "the robot reported no status moving from {:?}", pos)
+            }
+            ExploreError::UnknownStatus { pos, output } => write!(
+                f,
+                "the robot reported unknown status {} moving from {:?}",
+                output, pos
+            ),
+            ExploreError::BlockedBacktrack { pos } => {
+                write!(f, "the robot hit a wall backtracking from {:?}", pos)
+            }
+        }
+    }
+}
+
+impl Error for ExploreError {}
+
+/// Maps a robot's whole reachable area by walking it depth-first and backtracking.
+///
+/// `moves` holds the commands for north, south, west and east, in that order. The decoder
+/// turns the single output of each move into a status, or `None` for outputs the protocol
+/// doesn't allow; the robot stays put on a wall.
+#[derive(Debug, Clone)]
+pub struct Explorer<F> {
+    moves: [Word; 4],
+    decode: F,
+}
+
+const STEPS: [Point; 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+fn opposite(dir: usize) -> usize {
+    dir ^ 1
+}
+
+impl<F> Explorer<F>
+where
+    F: Fn(Word) -> Option<Status>,
+{
+    pub fn new(moves: [Word; 4], decode: F) -> Explorer<F> {
+        Explorer { moves, decode }
+    }
+
+    pub fn explore(&self, mut ic: Intcode) -> Result<World, ExploreError> {
+        let mut world = World::default();
+        world.map.insert((0, 0), Status::Open);
+
+        // Each entry is a position, the next direction to try there and the way back.
+        let mut stack = vec![((0, 0), 0, None)];
+
+        while let Some((pos, dir, back)) = stack.pop() {
+            if dir == STEPS.len() {
+                if let Some(back) = back {
+                    if self.step(&mut ic, pos, back)? == Status::Wall {
+                        return Err(ExploreError::BlockedBacktrack { pos });
+                    }
+                }
+                continue;
+            }
+
+            stack.push((pos, dir + 1, back));
+
+            let next = (pos.0 + STEPS[dir].0, pos.1 + STEPS[dir].1);
+            if world.map.contains_key(&next) {
+                continue;
+            }
+
+            let status = self.step(&mut ic, pos, dir)?;
+            world.map.insert(next, status);
+
+            if status == Status::Special {
+                world.specials.push(next);
+            }
+            if status != Status::Wall {
+                stack.push((next, 0, Some(opposite(dir))));
+            }
+        }
+
+        Ok(world)
+    }
+
+    fn step(&self, ic: &mut Intcode, pos: Point, dir: usize) -> Result<Status, ExploreError> {
+        ic.push_input(self.moves[dir]);
+        let (outs, _) = ic.run();
+        let &output = outs.first().ok_or(ExploreError::NoStatus { pos })?;
+
+        (self.decode)(output).ok_or(ExploreError::UnknownStatus { pos, output })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::compiler;
+
+    #[test]
+    fn explores_loops() {
+        // A 3x3 ring of floor around a single wall, with the special tile in a corner
+        let robot = "
+            fn open(x, y) {
+                if x < 0 || x > 2 || y < 0 || y > 2 {
+                    return 0;
+                }
+                return !(x == 1 && y == 1);
+            }
+
+            fn main() {
+                let x = 0;
+                let y = 0;
+                while 1 {
+                    let d = input();
+                    let nx = x + (d == 4) - (d == 3);
+                    let ny = y + (d == 2) - (d == 1);
+                    if open(nx, ny) {
+                        x = nx;
+                        y = ny;
+                        output(1 + (x == 2 && y == 0));
+                    } else {
+                        output(0);
+                    }
+                }
+            }
+        ";
+        let ic = Intcode::new(&compiler::compile(robot).unwrap());
+
+        let world = Explorer::new([1, 2, 3, 4], |w| match w {
+            0 => Some(Status::Wall),
+            1 => Some(Status::Open),
+            _ => Some(Status::Special),
+        })
+        .explore(ic)
+        .unwrap();
+
+        let count = |s| world.map.values().filter(|&&v| v == s).count();
+        assert_eq!(count(Status::Open), 7);
+        assert_eq!(count(Status::Wall), 13);
+        assert_eq!(world.specials, vec![(2, 0)]);
+        assert_eq!(world.map[&(2, 0)], Status::Special);
+    }
+
+    #[test]
+    fn protocol_violations() {
+        let decode = |w| match w {
+            0 => Some(Status::Wall),
+            1 => Some(Status::Open),
+            _ => None,
+        };
+        let explore =
+            |program: &str| Explorer::new([1, 2, 3, 4], decode).explore(Intcode::new(program));
+
+        // in [9]; jnz 1, 0
+        assert_eq!(
+            explore("3,9,1105,1,0").unwrap_err(),
+            ExploreError::NoStatus { pos: (0, 0) }
+        );
+        // in [9]; out 7; jnz 1, 0
+        assert_eq!(
+            explore("3,9,104,7,1105,1,0").unwrap_err(),
+            ExploreError::UnknownStatus {
+                pos: (0, 0),
+                output: 7
+            }
+        );
+        // Every move succeeds once, after which the robot is stuck
+        assert_eq!(
+            explore("3,20,104,1,3,20,104,0,1105,1,4").unwrap_err(),
+            ExploreError::BlockedBacktrack { pos: (0, -1) }
+        );
+    }
+}
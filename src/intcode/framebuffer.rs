@@ -0,0 +1,146 @@
+use super::Word;
+use crate::grid::SparseGrid;
+
+use std::collections::{BTreeSet, HashMap};
+
+/// A screen device fed with the `x, y, value` triples of a drawing program.
+///
+/// The image grows in every direction as cells are drawn. Coordinates registered with
+/// `special` are not part of the image and act as registers instead, e.g. a score display.
+#[derive(Debug, Clone)]
+pub struct Framebuffer {
+    image: SparseGrid<Word>,
+    registers: HashMap<(Word, Word), Option<Word>>,
+    changed: BTreeSet<(Word, Word)>,
+    pending: Vec<Word>,
+}
+
+impl Default for Framebuffer {
+    fn default() -> Framebuffer {
+        Framebuffer {
+            image: SparseGrid::new(0),
+            registers: HashMap::new(),
+            changed: BTreeSet::new(),
+            pending: vec![],
+        }
+    }
+}
+
+impl Framebuffer {
+    pub fn new() -> Framebuffer {
+        Framebuffer::default()
+    }
+
+    /// Sets the value of cells that have never been drawn.
+    pub fn background(mut self, background: Word) -> Framebuffer {
+        self.image = SparseGrid::new(background);
+        self
+    }
+
+    pub fn special(mut self, x: Word, y: Word) -> Framebuffer {
+        self.registers.insert((x, y), None);
+        self
+    }
+
+    /// Consumes output words as triples, keeping a trailing partial triple for the next call.
+    pub fn write(&mut self, outs: &[Word]) {
+        self.pending.extend_from_slice(outs);
+
+        let whole = self.pending.len() - self.pending.len() % 3;
+        let triples = self.pending.drain(..whole).collect::<Vec<_>>();
+
+        for t in triples.chunks_exact(3) {
+            self.draw(t[0], t[1], t[2]);
+        }
+    }
+
+    pub fn draw(&mut self, x: Word, y: Word, value: Word) {
+        if let Some(reg) = self.registers.get_mut(&(x, y)) {
+            *reg = Some(value);
+            return;
+        }
+
+        let cell = self.image.get_mut((x as i64, y as i64));
+        if *cell != value {
+            *cell = value;
+            self.changed.insert((x, y));
+        }
+    }
+
+    pub fn get(&self, x: Word, y: Word) -> Word {
+        *self.image.get((x as i64, y as i64))
+    }
+
+    pub fn register(&self, x: Word, y: Word) -> Option<Word> {
+        self.registers.get(&(x, y)).cloned().flatten()
+    }
+
+    /// Returns the cells whose value changed since the last call, in row-major order.
+    pub fn take_changes(&mut self) -> Vec<(Word, Word)> {
+        let mut changes = std::mem::take(&mut self.changed)
+            .into_iter()
+            .collect::<Vec<_>>();
+        changes.sort_by_key(|&(x, y)| (y, x));
+        changes
+    }
+
+    /// The top-left corner and the size of the image.
+    pub fn bounds(&self) -> ((Word, Word), (usize, usize)) {
+        let ((x, y), size) = self.image.bounds();
+        ((x as Word, y as Word), size)
+    }
+
+    pub fn positions(&self, value: Word) -> Vec<(Word, Word)> {
+        self.image
+            .iter()
+            .filter(|&(_, &w)| w == value)
+            .map(|((x, y), _)| (x as Word, y as Word))
+            .collect()
+    }
+
+    pub fn count(&self, value: Word) -> usize {
+        self.image.iter().filter(|&(_, &w)| w == value).count()
+    }
+
+    /// The image as rows, top to bottom.
+    pub fn image(&self) -> Vec<Vec<Word>> {
+        self.image.grid().rows().map(|row| row.to_vec()).collect()
+    }
+
+    pub fn render<F>(&self, f: F) -> String
+    where
+        F: Fn(Word) -> char,
+    {
+        self.image.grid().render(|&w| f(w))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grows_and_tracks_changes() {
+        let mut fb = Framebuffer::new().special(-1, 0);
+
+        fb.write(&[1, 1, 2, -1, 0, 500, 3]);
+        assert_eq!(fb.bounds(), ((1, 1), (1, 1)));
+        assert_eq!(fb.register(-1, 0), Some(500));
+
+        fb.write(&[0, 5, -2, -2, 7]);
+        assert_eq!(fb.bounds(), ((-2, -2), (6, 4)));
+        assert_eq!(fb.get(1, 1), 2);
+        assert_eq!(fb.get(3, 0), 5);
+        assert_eq!(fb.get(-2, -2), 7);
+        assert_eq!(fb.get(9, 9), 0);
+        assert_eq!(fb.take_changes(), vec![(-2, -2), (3, 0), (1, 1)]);
+
+        fb.write(&[1, 1, 2, 3, 0, 6]);
+        assert_eq!(fb.take_changes(), vec![(3, 0)]);
+        assert_eq!(fb.positions(6), vec![(3, 0)]);
+        assert_eq!(
+            fb.render(|w| if w == 0 { '.' } else { '#' }),
+            "#.....\n......\n.....#\n...#.."
+        );
+    }
+}
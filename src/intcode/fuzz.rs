@@ -0,0 +1,538 @@
+use super::{Engine, Intcode, StopCondition, Word};
+use crate::rng::Rng;
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+/// Loop counters, one per nesting depth.
+const COUNTERS: Word = 900;
+/// Holds the target of computed jumps.
+const TARGET: Word = 950;
+/// Scratch cells, also the relative base outside of `arb` groups.
+const DATA: Word = 1000;
+const DATA_LEN: i64 = 16;
+/// Cells far past the end of the program, to exercise memory growth.
+const FAR: Word = 4000;
+
+const MAX_DEPTH: usize = 2;
+const FUEL: usize = 100_000;
+
+/// A generated program and the inputs it is run with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    pub program: Vec<Word>,
+    pub inputs: Vec<Word>,
+}
+
+struct Generator {
+    rng: Rng,
+    code: Vec<Word>,
+    /// Addresses of immediate operands of arithmetic instructions, safe to overwrite.
+    immediates: Vec<usize>,
+    shift: i64,
+    reads: usize,
+}
+
+/// Generates a valid program whose loops all have constant trip counts, along with its inputs.
+///
+/// Programs mix arithmetic on scratch cells in every mode, I/O, counted loops, forward
+/// branches with constant and computed targets, relative base shifts and stores into the
+/// immediate operands of earlier instructions.
+pub fn generate(seed: u64) -> Case {
+    let mut g = Generator {
+        rng: Rng::new(seed),
+        code: vec![],
+        immediates: vec![],
+        shift: 0,
+        reads: 0,
+    };
+
+    g.emit(9, &[(1, DATA)]);
+    let len = g.rng.range(4, 24) as usize;
+    g.block(0, len);
+    g.code.push(99);
+
+    // Some programs run out of input and stop for more.
+    let n = if g.rng.chance(20) {
+        g.rng.below(g.reads as u64 + 1) as usize
+    } else {
+        g.reads * 16
+    };
+    let inputs = (0..n).map(|_| g.rng.range(-9, 9) as Word).collect();
+
+    Case {
+        program: g.code,
+        inputs,
+    }
+}
+
+impl Generator {
+    fn block(&mut self, depth: usize, len: usize) {
+        for _ in 0..len {
+            match self.rng.below(12) {
+                6 => {
+                    let dst = self.write();
+                    self.emit(3, &[dst]);
+                    self.reads += 1;
+                }
+                7 | 8 => {
+                    let src = self.read();
+                    self.emit(4, &[src]);
+                }
+                9 if depth < MAX_DEPTH => self.counted_loop(depth),
+                10 if depth < MAX_DEPTH => self.skip(depth),
+                11 if !self.immediates.is_empty() => {
+                    let dst = *self.rng.choose(&self.immediates) as Word;
+                    let (a, b) = (self.read(), self.imm());
+                    self.emit(1, &[a, b, (0, dst)]);
+                }
+                5 => {
+                    let d = self.rng.range(-4, 4);
+                    self.emit(9, &[(1, d as Word)]);
+                    self.shift = d;
+                    self.arithmetic();
+                    self.shift = 0;
+                    self.emit(9, &[(1, -d as Word)]);
+                }
+                _ => self.arithmetic(),
+            }
+        }
+    }
+
+    fn arithmetic(&mut self) {
+        let op = *self.rng.choose(&[1, 1, 2, 7, 8]);
+        let (a, b, c) = (self.read(), self.read(), self.write());
+        let addr = self.emit(op, &[a, b, c]);
+
+        for (i, &(mode, _)) in [a, b].iter().enumerate() {
+            if mode == 1 {
+                self.immediates.push(addr + 1 + i);
+            }
+        }
+    }
+
+    fn counted_loop(&mut self, depth: usize) {
+        let counter = COUNTERS + depth as Word;
+        let trips = self.rng.range(1, 4) as Word;
+
+        self.emit(1, &[(1, trips), (1, 0), (0, counter)]);
+        let start = self.code.len() as Word;
+        let len = self.rng.range(1, 5) as usize;
+        self.block(depth + 1, len);
+        self.emit(1, &[(0, counter), (1, -1), (0, counter)]);
+        self.emit(5, &[(0, counter), (1, start)]);
+    }
+
+    fn skip(&mut self, depth: usize) {
+        let op = *self.rng.choose(&[5, 6]);
+        let cond = self.read();
+
+        let patch = if self.rng.chance(30) {
+            let at = self.emit(1, &[(1, 0), (1, 0), (0, TARGET)]);
+            self.emit(op, &[cond, (0, TARGET)]);
+            at + 1
+        } else {
+            self.emit(op, &[cond, (1, 0)]) + 2
+        };
+
+        let len = self.rng.range(1, 4) as usize;
+        self.block(depth + 1, len);
+        self.code[patch] = self.code.len() as Word;
+    }
+
+    fn imm(&mut self) -> (Word, Word) {
+        (1, self.rng.range(-9, 9) as Word)
+    }
+
+    fn cell(&mut self) -> (Word, Word) {
+        match self.rng.below(10) {
+            0 => (0, FAR + self.rng.below(4) as Word),
+            1..=4 => (2, (self.rng.range(0, DATA_LEN - 1) - self.shift) as Word),
+            _ => (0, DATA + self.rng.range(0, DATA_LEN - 1) as Word),
+        }
+    }
+
+    fn read(&mut self) -> (Word, Word) {
+        if self.rng.chance(30) {
+            self.imm()
+        } else {
+            self.cell()
+        }
+    }
+
+    fn write(&mut self) -> (Word, Word) {
+        self.cell()
+    }
+
+    /// Appends an instruction with `(mode, word)` operands and returns its address.
+    fn emit(&mut self, opcode: Word, operands: &[(Word, Word)]) -> usize {
+        let addr = self.code.len();
+        let modes = operands
+            .iter()
+            .enumerate()
+            .map(|(i, &(mode, _))| mode * 10i128.pow(i as u32 + 2))
+            .sum::<Word>();
+
+        self.code.push(opcode + modes);
+        self.code.extend(operands.iter().map(|&(_, w)| w));
+        addr
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Stop {
+    Halt,
+    NeedInput,
+    Fault,
+}
+
+/// The observable state of a machine once it stops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub outputs: Vec<Word>,
+    pub memory: Vec<Word>,
+    pub pc: usize,
+    pub rb: Word,
+    pub stop: Stop,
+}
+
+struct Reference<'a> {
+    memory: Vec<Word>,
+    pc: usize,
+    rb: Word,
+    inputs: &'a [Word],
+    outputs: Vec<Word>,
+}
+
+/// Runs a program with a straightforward transcription of the puzzle rules.
+///
+/// Returns `None` for runs the harness can't compare: arithmetic overflow, negative
+/// addresses, or more than `fuel` instructions.
+pub fn reference(program: &[Word], inputs: &[Word], fuel: usize) -> Option<Outcome> {
+    let mut m = Reference {
+        memory: program.to_vec(),
+        pc: 0,
+        rb: 0,
+        inputs,
+        outputs: vec![],
+    };
+
+    for _ in 0..fuel {
+        match m.step()? {
+            Some(stop) => {
+                return Some(Outcome {
+                    outputs: m.outputs,
+                    memory: trimmed(m.memory),
+                    pc: m.pc,
+                    rb: m.rb,
+                    stop,
+                })
+            }
+            None => continue,
+        }
+    }
+
+    None
+}
+
+impl Reference<'_> {
+    fn get(&self, addr: usize) -> Word {
+        self.memory.get(addr).copied().unwrap_or(0)
+    }
+
+    fn mode(&self, i: usize) -> Word {
+        self.get(self.pc) / 10i128.pow(i as u32 + 2) % 10
+    }
+
+    fn addr(&self, i: usize) -> Option<usize> {
+        let raw = self.get(self.pc + 1 + i);
+        let addr = if self.mode(i) == 2 {
+            self.rb.checked_add(raw)?
+        } else {
+            raw
+        };
+        usize::try_from(addr).ok()
+    }
+
+    fn read(&self, i: usize) -> Option<Word> {
+        if self.mode(i) == 1 {
+            Some(self.get(self.pc + 1 + i))
+        } else {
+            self.addr(i).map(|addr| self.get(addr))
+        }
+    }
+
+    fn write(&mut self, i: usize, w: Word) -> Option<()> {
+        let addr = self.addr(i)?;
+        if addr >= self.memory.len() {
+            self.memory.resize(addr + 1, 0);
+        }
+        self.memory[addr] = w;
+        Some(())
+    }
+
+    /// Executes one instruction, returning `Some(None)` to keep going.
+    fn step(&mut self) -> Option<Option<Stop>> {
+        let opcode = self.get(self.pc) % 100;
+        let (arity, writes) = match opcode {
+            1 | 2 | 7 | 8 => (3, true),
+            3 => (1, true),
+            4 | 9 => (1, false),
+            5 | 6 => (2, false),
+            99 => return Some(Some(Stop::Halt)),
+            _ => return Some(Some(Stop::Fault)),
+        };
+
+        if (0..arity).any(|i| self.mode(i) > 2) || (writes && self.mode(arity - 1) == 1) {
+            return Some(Some(Stop::Fault));
+        }
+
+        let mut next = self.pc + 1 + arity;
+
+        match opcode {
+            1 => self.write(2, self.read(0)?.checked_add(self.read(1)?)?)?,
+            2 => self.write(2, self.read(0)?.checked_mul(self.read(1)?)?)?,
+            7 => self.write(2, (self.read(0)? < self.read(1)?) as Word)?,
+            8 => self.write(2, (self.read(0)? == self.read(1)?) as Word)?,
+            3 => match self.inputs.split_first() {
+                Some((&input, rest)) => {
+                    self.inputs = rest;
+                    self.write(0, input)?
+                }
+                None => return Some(Some(Stop::NeedInput)),
+            },
+            4 => self.outputs.push(self.read(0)?),
+            5 | 6 => {
+                if (self.read(0)? != 0) == (opcode == 5) {
+                    next = usize::try_from(self.read(1)?).ok()?;
+                }
+            }
+            9 => self.rb = self.rb.checked_add(self.read(0)?)?,
+            _ => unreachable!(),
+        }
+
+        self.pc = next;
+        Some(None)
+    }
+}
+
+fn trimmed(mut memory: Vec<Word>) -> Vec<Word> {
+    let len = memory.iter().rposition(|&w| w != 0).map_or(0, |i| i + 1);
+    memory.truncate(len);
+    memory
+}
+
+fn run(engine: Engine, case: &Case) -> Outcome {
+    let mut ic = Intcode::new(&program_text(&case.program))
+        .engine(engine)
+        .inputs(&case.inputs);
+    let (outputs, sc) = ic.run();
+
+    Outcome {
+        outputs,
+        memory: trimmed(ic.memory),
+        pc: ic.pc,
+        rb: ic.rb as Word,
+        stop: match sc {
+            StopCondition::Halt => Stop::Halt,
+            StopCondition::NeedInput => Stop::NeedInput,
+            StopCondition::Fault(_) => Stop::Fault,
+        },
+    }
+}
+
+fn program_text(program: &[Word]) -> String {
+    program
+        .iter()
+        .map(|w| w.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub seed: u64,
+    pub engine: Engine,
+    pub case: Case,
+    pub detail: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "seed {} ({:?}): {}", self.seed, self.engine, self.detail)
+    }
+}
+
+impl Failure {
+    /// Writes `seed-N.int` with the program and `seed-N.txt` with the inputs and the
+    /// mismatch, returning the path of the program.
+    pub fn save(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+
+        let program = dir.join(format!("seed-{}.int", self.seed));
+        fs::write(&program, program_text(&self.case.program) + "\n")?;
+        fs::write(
+            dir.join(format!("seed-{}.txt", self.seed)),
+            format!(
+                "engine: {:?}\ninputs: {}\n{}\n",
+                self.engine,
+                program_text(&self.case.inputs),
+                self.detail
+            ),
+        )?;
+
+        Ok(program)
+    }
+}
+
+#[derive(Debug)]
+pub enum Verdict {
+    Pass,
+    /// The reference could not run the program to completion.
+    Skipped,
+    Fail(Box<Failure>),
+}
+
+/// Runs the program generated from `seed` on every engine and compares each run with the
+/// reference interpreter.
+pub fn check(seed: u64) -> Verdict {
+    let case = generate(seed);
+    let expected = match reference(&case.program, &case.inputs, FUEL) {
+        Some(outcome) => outcome,
+        None => return Verdict::Skipped,
+    };
+
+    for &engine in [Engine::Interpreter, Engine::Threaded].iter() {
+        let detail = match panic::catch_unwind(AssertUnwindSafe(|| run(engine, &case))) {
+            Ok(actual) if actual == expected => continue,
+            Ok(actual) => diff(&expected, &actual),
+            Err(_) => "panicked".to_string(),
+        };
+
+        return Verdict::Fail(Box::new(Failure {
+            seed,
+            engine,
+            case,
+            detail,
+        }));
+    }
+
+    Verdict::Pass
+}
+
+fn diff(expected: &Outcome, actual: &Outcome) -> String {
+    let mut lines = vec![];
+
+    if expected.stop != actual.stop {
+        lines.push(format!("stop: {:?} != {:?}", actual.stop, expected.stop));
+    }
+    if (expected.pc, expected.rb) != (actual.pc, actual.rb) {
+        lines.push(format!(
+            "pc, rb: {}, {} != {}, {}",
+            actual.pc, actual.rb, expected.pc, expected.rb
+        ));
+    }
+    if expected.outputs != actual.outputs {
+        lines.push(format!(
+            "outputs: {:?} != {:?}",
+            actual.outputs, expected.outputs
+        ));
+    }
+    if let Some(addr) = (0..expected.memory.len().max(actual.memory.len()))
+        .find(|&i| expected.memory.get(i) != actual.memory.get(i))
+    {
+        lines.push(format!(
+            "memory[{}]: {:?} != {:?}",
+            addr,
+            actual.memory.get(addr),
+            expected.memory.get(addr)
+        ));
+    }
+
+    lines.join("\n")
+}
+
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub passed: usize,
+    pub skipped: usize,
+    pub failures: Vec<Failure>,
+}
+
+pub fn fuzz(seeds: Range<u64>) -> Summary {
+    let mut summary = Summary::default();
+
+    for seed in seeds {
+        match check(seed) {
+            Verdict::Pass => summary.passed += 1,
+            Verdict::Skipped => summary.skipped += 1,
+            Verdict::Fail(failure) => summary.failures.push(*failure),
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn engines_agree_with_reference() {
+        let summary = fuzz(0..2000);
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/fuzz");
+
+        for failure in summary.failures.iter() {
+            failure.save(&dir).unwrap();
+        }
+
+        assert!(
+            summary.failures.is_empty(),
+            "failures saved to {}:\n{}",
+            dir.display(),
+            summary
+                .failures
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        assert!(summary.skipped * 20 < summary.passed, "{:?}", summary);
+    }
+
+    #[test]
+    fn reference_runs_puzzle_programs() {
+        let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        let program = Intcode::parse(quine);
+        let outcome = reference(&program, &[], FUEL).unwrap();
+
+        assert_eq!(outcome.outputs, program);
+        assert_eq!(outcome.stop, Stop::Halt);
+
+        let day9 = Intcode::parse(include_str!("../../res/9.txt"));
+        assert_eq!(
+            reference(&day9, &[1], FUEL).unwrap().outputs,
+            vec![3_345_854_957]
+        );
+        assert_eq!(reference(&day9, &[], FUEL).unwrap().stop, Stop::NeedInput);
+        assert_eq!(
+            reference(&[1, 0, 0, 0, 42], &[], FUEL).unwrap().stop,
+            Stop::Fault
+        );
+    }
+
+    #[test]
+    fn generated_programs_are_reproducible() {
+        assert_eq!(generate(7), generate(7));
+        assert_ne!(generate(7), generate(8));
+
+        let case = generate(3);
+        assert_eq!(case.program[..2], [109, DATA]);
+        assert_eq!(case.program.last(), Some(&99));
+    }
+}
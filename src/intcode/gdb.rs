@@ -0,0 +1,524 @@
+use super::{Intcode, StopCondition, Word};
+
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target>
+  <feature name="org.aoc19.intcode">
+    <reg name="pc" bitsize="64" type="code_ptr"/>
+    <reg name="rb" bitsize="64" type="data_ptr"/>
+  </feature>
+</target>
+"#;
+
+/// The largest packet payload the stub accepts or sends.
+const MAX_PAYLOAD: usize = 0x4000;
+
+/// Steps to run between checks for an interrupt from the debugger.
+const INTERRUPT_POLL: usize = 4096;
+
+/// A GDB Remote Serial Protocol stub for `Intcode`.
+///
+/// The target is presented as a machine with 64-bit little-endian memory cells, each word of
+/// Intcode memory occupying 8 bytes: byte address `8 * n` holds word `n`, truncated to 64 bits.
+/// Register 0 is `pc` and register 1 is `rb`, both reported as byte addresses. Program output is
+/// forwarded as console output, and inputs are provided with `monitor input <words>`. A running
+/// `continue` can be stopped with the usual interrupt.
+#[derive(Debug)]
+pub struct GdbStub {
+    ic: Intcode,
+    breakpoints: BTreeSet<usize>,
+    done: bool,
+}
+
+impl GdbStub {
+    pub fn new(ic: Intcode) -> GdbStub {
+        GdbStub {
+            ic,
+            breakpoints: BTreeSet::new(),
+            done: false,
+        }
+    }
+
+    pub fn machine(&self) -> &Intcode {
+        &self.ic
+    }
+
+    /// Accepts a single debugger connection on `listener` and serves it until it detaches or
+    /// kills the target.
+    pub fn serve(&mut self, listener: &TcpListener) -> io::Result<()> {
+        let (stream, _) = listener.accept()?;
+        self.serve_stream(stream)
+    }
+
+    pub fn serve_stream(&mut self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+
+        while !self.done {
+            let packet = match read_packet(&mut reader, &mut writer)? {
+                Some(packet) => packet,
+                None => break,
+            };
+
+            let replies = if packet.starts_with('c') {
+                self.resume(false, &mut || interrupted(&mut reader).unwrap_or(false))
+            } else {
+                self.handle_packet(&packet)
+            };
+
+            for reply in replies {
+                writer.write_all(frame(&reply).as_bytes())?;
+            }
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Handles one packet payload, returning the payloads to send back in order.
+    pub fn handle_packet(&mut self, packet: &str) -> Vec<String> {
+        let (cmd, args) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+
+        match cmd {
+            "?" => vec!["S05".to_string()],
+            "g" => vec![format!(
+                "{}{}",
+                hex_word(self.ic.pc as Word * 8),
+                hex_word(self.ic.rb as Word * 8)
+            )],
+            "G" => {
+                let regs = parse_hex_bytes(args).unwrap_or_default();
+                if regs.len() != 16 {
+                    return vec!["E01".to_string()];
+                }
+                self.set_register(0, from_le(&regs[..8]));
+                self.set_register(1, from_le(&regs[8..]));
+                vec!["OK".to_string()]
+            }
+            "p" => match usize::from_str_radix(args, 16) {
+                Ok(0) => vec![hex_word(self.ic.pc as Word * 8)],
+                Ok(1) => vec![hex_word(self.ic.rb as Word * 8)],
+                _ => vec!["E01".to_string()],
+            },
+            "P" => {
+                let mut parts = args.splitn(2, '=');
+                let reg = parts.next().and_then(|r| usize::from_str_radix(r, 16).ok());
+                let val = parts.next().and_then(parse_hex_bytes);
+
+                match (reg, val) {
+                    (Some(reg), Some(val)) if reg < 2 && val.len() == 8 => {
+                        self.set_register(reg, from_le(&val));
+                        vec!["OK".to_string()]
+                    }
+                    _ => vec!["E01".to_string()],
+                }
+            }
+            "m" => match parse_range(args) {
+                Some((addr, len)) => {
+                    let bytes = (addr..addr + len)
+                        .map(|a| format!("{:02x}", self.read_byte(a)))
+                        .collect::<String>();
+                    vec![bytes]
+                }
+                None => vec!["E01".to_string()],
+            },
+            "M" => {
+                let mut parts = args.splitn(2, ':');
+                let range = parts.next().and_then(parse_range);
+                let data = parts.next().and_then(parse_hex_bytes);
+
+                match (range, data) {
+                    (Some((addr, len)), Some(data)) if data.len() == len => {
+                        for (i, &b) in data.iter().enumerate() {
+                            self.write_byte(addr + i, b);
+                        }
+                        self.ic.cache.clear();
+                        vec!["OK".to_string()]
+                    }
+                    _ => vec!["E01".to_string()],
+                }
+            }
+            "c" => self.resume(false, &mut || false),
+            "s" => self.resume(true, &mut || false),
+            "Z" | "z" => {
+                let mut parts = args.split(',');
+                let kind = parts.next();
+                let addr = parts.next().and_then(|a| usize::from_str_radix(a, 16).ok());
+
+                match (kind, addr) {
+                    (Some("0"), Some(addr)) => {
+                        if cmd == "Z" {
+                            self.breakpoints.insert(addr / 8);
+                        } else {
+                            self.breakpoints.remove(&(addr / 8));
+                        }
+                        vec!["OK".to_string()]
+                    }
+                    _ => vec![String::new()],
+                }
+            }
+            "H" => vec!["OK".to_string()],
+            "D" => {
+                self.done = true;
+                vec!["OK".to_string()]
+            }
+            "k" => {
+                self.done = true;
+                vec![]
+            }
+            "q" => self.query(packet),
+            _ => vec![String::new()],
+        }
+    }
+
+    fn query(&mut self, packet: &str) -> Vec<String> {
+        if packet.starts_with("qSupported") {
+            vec![format!("PacketSize={:x};qXfer:features:read+", MAX_PAYLOAD)]
+        } else if packet == "qAttached" {
+            vec!["1".to_string()]
+        } else if packet == "qfThreadInfo" {
+            vec!["m1".to_string()]
+        } else if packet == "qsThreadInfo" {
+            vec!["l".to_string()]
+        } else if packet == "qC" {
+            vec!["QC1".to_string()]
+        } else if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            match parse_range(range) {
+                Some((offset, len)) => {
+                    let start = offset.min(TARGET_XML.len());
+                    let end = (offset + len).min(TARGET_XML.len());
+                    let more = if end < TARGET_XML.len() { "m" } else { "l" };
+                    vec![format!("{}{}", more, &TARGET_XML[start..end])]
+                }
+                None => vec!["E01".to_string()],
+            }
+        } else if let Some(cmd) = packet.strip_prefix("qRcmd,") {
+            let cmd = parse_hex_bytes(cmd)
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .unwrap_or_default();
+            self.monitor(&cmd)
+        } else {
+            vec![String::new()]
+        }
+    }
+
+    fn monitor(&mut self, cmd: &str) -> Vec<String> {
+        let mut words = cmd.split_whitespace();
+
+        match words.next() {
+            Some("input") => match words.map(str::parse).collect::<Result<Vec<Word>, _>>() {
+                Ok(inputs) => {
+                    for input in inputs {
+                        self.ic.push_input(input);
+                    }
+                    vec!["OK".to_string()]
+                }
+                Err(_) => vec![console("usage: monitor input <words>\n"), "OK".to_string()],
+            },
+            _ => vec![console("commands: input <words>\n"), "OK".to_string()],
+        }
+    }
+
+    /// Runs the machine until it stops, `single` step is done or `interrupted` says so; the
+    /// latter is polled every few thousand steps.
+    fn resume(&mut self, single: bool, interrupted: &mut dyn FnMut() -> bool) -> Vec<String> {
+        let mut outs = Vec::new();
+        let mut replies = Vec::new();
+
+        let mut steps = 0usize;
+
+        let stop = loop {
+            let sc = self.ic.step(&mut outs);
+
+            for out in outs.drain(..) {
+                replies.push(console(&format!("{}\n", out)));
+            }
+
+            match sc {
+                Some(StopCondition::Halt) => break "W00".to_string(),
+                Some(StopCondition::NeedInput) => {
+                    replies.push(console("waiting for input\n"));
+                    break "S05".to_string();
+                }
+                Some(StopCondition::Fault(fault)) => {
+                    replies.push(console(&format!("{}\n", fault)));
+                    break "S04".to_string();
+                }
+                None if single || self.breakpoints.contains(&self.ic.pc) => {
+                    break "S05".to_string()
+                }
+                None => (),
+            }
+
+            steps += 1;
+            if steps.is_multiple_of(INTERRUPT_POLL) && interrupted() {
+                break "S02".to_string();
+            }
+        };
+
+        replies.push(stop);
+        replies
+    }
+
+    fn set_register(&mut self, reg: usize, val: Word) {
+        let val = usize::try_from(val / 8).unwrap_or(0);
+        match reg {
+            0 => self.ic.pc = val,
+            _ => self.ic.rb = val,
+        }
+    }
+
+    fn read_byte(&self, addr: usize) -> u8 {
+        let word = self.ic.memory.get(addr / 8).cloned().unwrap_or(0) as i64;
+        word.to_le_bytes()[addr % 8]
+    }
+
+    fn write_byte(&mut self, addr: usize, b: u8) {
+        let idx = addr / 8;
+        if idx >= self.ic.memory.len() {
+            self.ic.memory.resize(idx + 1, 0);
+        }
+
+        let mut bytes = (self.ic.memory[idx] as i64).to_le_bytes();
+        bytes[addr % 8] = b;
+        self.ic.memory[idx] = Word::from(i64::from_le_bytes(bytes));
+    }
+}
+
+fn checksum(payload: &str) -> u8 {
+    payload.bytes().fold(0, |sum, b| sum.wrapping_add(b))
+}
+
+fn frame(payload: &str) -> String {
+    format!("${}#{:02x}", payload, checksum(payload))
+}
+
+fn console(text: &str) -> String {
+    let hex = text
+        .bytes()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!("O{}", hex)
+}
+
+fn hex_word(w: Word) -> String {
+    (w as i64)
+        .to_le_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn from_le(bytes: &[u8]) -> Word {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(bytes);
+    Word::from(i64::from_le_bytes(buf))
+}
+
+fn parse_hex_bytes(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Parses an `addr,length` pair, rejecting ranges past the end of the address space or
+/// longer than a packet can carry.
+fn parse_range(s: &str) -> Option<(usize, usize)> {
+    let mut parts = s.splitn(2, ',');
+    let addr = usize::from_str_radix(parts.next()?, 16).ok()?;
+    let len = usize::from_str_radix(parts.next()?, 16).ok()?;
+    addr.checked_add(len).filter(|_| len <= MAX_PAYLOAD)?;
+    Some((addr, len))
+}
+
+/// Checks without blocking whether the debugger sent the interrupt byte, consuming it if so.
+fn interrupted(reader: &mut BufReader<TcpStream>) -> io::Result<bool> {
+    if reader.buffer().is_empty() {
+        reader.get_ref().set_nonblocking(true)?;
+        let filled = reader.fill_buf().map(|_| ());
+        reader.get_ref().set_nonblocking(false)?;
+
+        match filled {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+            other => other?,
+        }
+    }
+
+    if reader.buffer().first() == Some(&0x03) {
+        reader.consume(1);
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// Reads the next packet payload, acknowledging it. Returns `None` once the peer hangs up.
+fn read_packet<R: Read, W: Write>(reader: &mut R, writer: &mut W) -> io::Result<Option<String>> {
+    let mut byte = [0; 1];
+
+    loop {
+        loop {
+            if reader.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+            if byte[0] == b'$' {
+                break;
+            }
+        }
+
+        let mut payload = Vec::new();
+        loop {
+            if reader.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+            if byte[0] == b'#' {
+                break;
+            }
+            payload.push(byte[0]);
+        }
+
+        let mut sum = [0; 2];
+        reader.read_exact(&mut sum)?;
+
+        let payload = String::from_utf8_lossy(&payload).into_owned();
+        let expected = std::str::from_utf8(&sum)
+            .ok()
+            .and_then(|s| u8::from_str_radix(s, 16).ok());
+
+        if expected == Some(checksum(&payload)) {
+            writer.write_all(b"+")?;
+            return Ok(Some(payload));
+        }
+
+        writer.write_all(b"-")?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Client {
+        fn request(&mut self, payload: &str) -> String {
+            self.writer.write_all(frame(payload).as_bytes()).unwrap();
+
+            let mut ack = [0; 1];
+            self.reader.read_exact(&mut ack).unwrap();
+            assert_eq!(ack[0], b'+');
+
+            self.reply()
+        }
+
+        fn reply(&mut self) -> String {
+            read_packet(&mut self.reader, &mut self.writer)
+                .unwrap()
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn framing() {
+        assert_eq!(frame("OK"), "$OK#9a");
+        assert_eq!(console("42\n"), "O34320a");
+        assert_eq!(hex_word(-2), "feffffffffffffff");
+        assert_eq!(parse_range("a0,8"), Some((160, 8)));
+        assert_eq!(parse_range("ffffffffffffffff,10"), None);
+
+        let mut stub = GdbStub::new(Intcode::new("99"));
+        assert_eq!(stub.handle_packet("mffffffffffffffff,10"), vec!["E01"]);
+        assert_eq!(stub.handle_packet("Mffffffffffffffff,1:00"), vec!["E01"]);
+    }
+
+    #[test]
+    fn scripted_session() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // in [20]; mul [20], 2, [20]; out [20]; halt
+        let server = thread::spawn(move || {
+            let mut stub = GdbStub::new(Intcode::new("3,20,1002,20,2,20,4,20,99"));
+            stub.serve(&listener).unwrap();
+            stub.machine().value(20)
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut gdb = Client {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            writer: stream,
+        };
+
+        assert!(gdb
+            .request("qSupported:xmlRegisters=i386")
+            .starts_with("PacketSize"));
+        assert_eq!(gdb.request("?"), "S05");
+        assert_eq!(gdb.request("g"), "0".repeat(32));
+        assert_eq!(gdb.request("m0,10"), "03000000000000001400000000000000");
+        assert_eq!(gdb.request("Z0,10,1"), "OK");
+
+        assert_eq!(gdb.request("c"), console("waiting for input\n"));
+        assert_eq!(gdb.reply(), "S05");
+        let cmd = "input 21"
+            .bytes()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        assert_eq!(gdb.request(&format!("qRcmd,{}", cmd)), "OK");
+
+        assert_eq!(gdb.request("c"), "S05");
+        assert_eq!(gdb.request("p0"), "1000000000000000");
+        assert_eq!(gdb.request("s"), "S05");
+        assert_eq!(gdb.request("ma0,8"), "2a00000000000000");
+
+        assert_eq!(gdb.request("c"), console("42\n"));
+        assert_eq!(gdb.reply(), "W00");
+
+        assert_eq!(gdb.request("Ma0,8:ffffffffffffffff"), "OK");
+        assert_eq!(gdb.request("D"), "OK");
+
+        assert_eq!(server.join().unwrap(), -1);
+    }
+
+    #[test]
+    fn interrupts_a_running_program() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // jnz 1, 0
+        let server = thread::spawn(move || {
+            let mut stub = GdbStub::new(Intcode::new("1105,1,0"));
+            stub.serve(&listener).unwrap();
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut gdb = Client {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            writer: stream,
+        };
+
+        gdb.writer.write_all(frame("c").as_bytes()).unwrap();
+        let mut ack = [0; 1];
+        gdb.reader.read_exact(&mut ack).unwrap();
+        assert_eq!(ack[0], b'+');
+
+        gdb.writer.write_all(&[0x03]).unwrap();
+        assert_eq!(gdb.reply(), "S02");
+        assert_eq!(gdb.request("p0"), "0000000000000000");
+        assert_eq!(gdb.request("D"), "OK");
+
+        server.join().unwrap();
+    }
+}
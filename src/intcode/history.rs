@@ -0,0 +1,221 @@
+use super::{Intcode, Word};
+
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone)]
+pub struct History {
+    frames: VecDeque<Frame>,
+    window: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Frame {
+    pc: usize,
+    rb: usize,
+    len: usize,
+    writes: Vec<(usize, Word)>,
+    input: Option<Word>,
+}
+
+impl History {
+    pub fn new(window: usize) -> History {
+        History {
+            frames: VecDeque::with_capacity(window.min(1024)),
+            window,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Returns the address of the most recent recorded instruction that wrote to `pos`.
+    pub fn last_write_to(&self, pos: usize) -> Option<usize> {
+        self.frames
+            .iter()
+            .rev()
+            .find(|frame| frame.writes.iter().any(|&(idx, _)| idx == pos))
+            .map(|frame| frame.pc)
+    }
+
+    pub(super) fn begin(&mut self, pc: usize, rb: usize, len: usize) {
+        if self.window == 0 {
+            return;
+        }
+
+        self.frames.push_back(Frame {
+            pc,
+            rb,
+            len,
+            writes: Vec::new(),
+            input: None,
+        });
+    }
+
+    pub(super) fn write(&mut self, pos: usize, old: Word) {
+        if let Some(frame) = self.frames.back_mut() {
+            frame.writes.push((pos, old));
+        }
+    }
+
+    pub(super) fn consume(&mut self, input: Word) {
+        if let Some(frame) = self.frames.back_mut() {
+            frame.input = Some(input);
+        }
+    }
+
+    /// Keeps the frame of a finished instruction, evicting the oldest one past the window.
+    pub(super) fn commit(&mut self) {
+        if self.frames.len() > self.window {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Drops the frame of an instruction that stopped the machine instead of running.
+    pub(super) fn discard(&mut self) {
+        if self.window != 0 {
+            self.frames.pop_back();
+        }
+    }
+}
+
+impl Intcode {
+    pub fn record(mut self, window: usize) -> Intcode {
+        self.history = Some(History::new(window));
+        self
+    }
+
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    pub fn step_back(&mut self) -> bool {
+        let frame = match self.history.as_mut().and_then(|h| h.frames.pop_back()) {
+            Some(frame) => frame,
+            None => return false,
+        };
+
+        self.cache.clear();
+
+        for &(pos, old) in frame.writes.iter().rev() {
+            self.memory[pos] = old;
+        }
+        self.memory.truncate(frame.len);
+
+        if let Some(input) = frame.input {
+            self.inputs.push_front(input);
+        }
+
+        self.pc = frame.pc;
+        self.rb = frame.rb;
+
+        true
+    }
+
+    pub fn run_back_to(&mut self, breakpoint: usize) -> bool {
+        while self.step_back() {
+            if self.pc == breakpoint {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::StopCondition;
+
+    fn snapshot(ic: &Intcode) -> (Vec<Word>, Vec<Word>, usize, usize) {
+        (
+            ic.memory.clone(),
+            ic.inputs.iter().cloned().collect(),
+            ic.pc,
+            ic.rb,
+        )
+    }
+
+    #[test]
+    fn step_back_restores_state() {
+        let mut ic = Intcode::new(include_str!("../../res/9.txt"))
+            .inputs(&[1])
+            .record(usize::MAX);
+        let mut outs = Vec::new();
+        let mut snapshots = Vec::new();
+
+        loop {
+            snapshots.push(snapshot(&ic));
+            if ic.step(&mut outs).is_some() {
+                snapshots.pop();
+                break;
+            }
+        }
+        assert_eq!(ic.history().unwrap().len(), snapshots.len());
+
+        while let Some(expected) = snapshots.pop() {
+            assert!(ic.step_back());
+            assert_eq!(snapshot(&ic), expected);
+        }
+        assert!(!ic.step_back());
+    }
+
+    #[test]
+    fn history_window_is_bounded() {
+        let mut ic = Intcode::new(include_str!("../../res/5.txt"))
+            .inputs(&[5])
+            .record(8);
+        let mut outs = Vec::new();
+
+        for _ in 0..32 {
+            ic.step(&mut outs);
+        }
+        assert_eq!(ic.history().unwrap().len(), 8);
+
+        for _ in 0..8 {
+            assert!(ic.step_back());
+        }
+        assert!(!ic.step_back());
+    }
+
+    #[test]
+    fn stops_keep_a_full_window() {
+        // in [9]; out [9]; jnz 1, 0
+        let mut ic = Intcode::new("3,9,4,9,1105,1,0").record(4);
+        let mut outs = Vec::new();
+
+        // Each round runs three instructions, then stops asking for more input
+        for input in 0..3 {
+            ic.push_input(input);
+            while ic.step(&mut outs).is_none() {}
+        }
+        assert_eq!(ic.history().unwrap().len(), 4);
+
+        let mut pcs = vec![];
+        while ic.step_back() {
+            pcs.push(ic.pc());
+        }
+        assert_eq!(pcs, vec![4, 2, 0, 4]);
+    }
+
+    #[test]
+    fn run_back_to_breakpoint() {
+        let mut ic = Intcode::new("3,9,8,9,10,9,4,9,99,-1,8")
+            .inputs(&[8])
+            .record(16);
+
+        assert_eq!(ic.run(), (vec![1], StopCondition::Halt));
+        assert_eq!(ic.history().unwrap().last_write_to(9), Some(2));
+
+        assert!(ic.run_back_to(0));
+        assert_eq!(ic.value(9), -1);
+        assert!(!ic.run_back_to(0));
+
+        ic.push_input(7);
+        assert_eq!(ic.run(), (vec![1], StopCondition::Halt));
+    }
+}
@@ -0,0 +1,328 @@
+use super::Word;
+
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    Add,
+    Mul,
+    Input,
+    Output,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    AdjustBase,
+    Halt,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub modes: [Mode; 3],
+}
+
+/// The instruction set as introduced by the puzzles: day 2 only has `add`, `mul` and `halt` in
+/// position mode, day 5 adds I/O, jumps, comparisons and immediate mode, and day 9 adds relative
+/// mode and `arb`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Day2,
+    Day5,
+    Day9,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Isa {
+    pub level: Level,
+    /// Rejects non-zero mode digits that do not belong to any parameter.
+    pub strict: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    InvalidOpcode(Word),
+    InvalidMode(Word, usize),
+    UnsupportedOpcode(Word, Level),
+    UnsupportedMode(Word, usize, Level),
+    ImmediateDestination(Word),
+    TrailingModes(Word),
+}
+
+impl Level {
+    pub fn supports_opcode(self, opcode: Opcode) -> bool {
+        match opcode {
+            Opcode::Add | Opcode::Mul | Opcode::Halt => true,
+            Opcode::AdjustBase => self >= Level::Day9,
+            _ => self >= Level::Day5,
+        }
+    }
+
+    pub fn supports_mode(self, mode: Mode) -> bool {
+        match mode {
+            Mode::Position => true,
+            Mode::Immediate => self >= Level::Day5,
+            Mode::Relative => self >= Level::Day9,
+        }
+    }
+}
+
+impl Default for Isa {
+    fn default() -> Isa {
+        Isa {
+            level: Level::Day9,
+            strict: false,
+        }
+    }
+}
+
+impl Opcode {
+    pub fn from_word(w: Word) -> Option<Opcode> {
+        Some(match w {
+            1 => Opcode::Add,
+            2 => Opcode::Mul,
+            3 => Opcode::Input,
+            4 => Opcode::Output,
+            5 => Opcode::JumpIfTrue,
+            6 => Opcode::JumpIfFalse,
+            7 => Opcode::LessThan,
+            8 => Opcode::Equals,
+            9 => Opcode::AdjustBase,
+            99 => Opcode::Halt,
+            _ => return None,
+        })
+    }
+
+    pub fn code(self) -> Word {
+        match self {
+            Opcode::Add => 1,
+            Opcode::Mul => 2,
+            Opcode::Input => 3,
+            Opcode::Output => 4,
+            Opcode::JumpIfTrue => 5,
+            Opcode::JumpIfFalse => 6,
+            Opcode::LessThan => 7,
+            Opcode::Equals => 8,
+            Opcode::AdjustBase => 9,
+            Opcode::Halt => 99,
+        }
+    }
+
+    pub fn arity(self) -> usize {
+        match self {
+            Opcode::Add | Opcode::Mul | Opcode::LessThan | Opcode::Equals => 3,
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => 2,
+            Opcode::Input | Opcode::Output | Opcode::AdjustBase => 1,
+            Opcode::Halt => 0,
+        }
+    }
+
+    /// Whether the last parameter of the instruction is a write destination.
+    pub fn writes(self) -> bool {
+        matches!(
+            self,
+            Opcode::Add | Opcode::Mul | Opcode::LessThan | Opcode::Equals | Opcode::Input
+        )
+    }
+
+    pub fn is_jump(self) -> bool {
+        self == Opcode::JumpIfTrue || self == Opcode::JumpIfFalse
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Opcode::Add => "add",
+            Opcode::Mul => "mul",
+            Opcode::Input => "in",
+            Opcode::Output => "out",
+            Opcode::JumpIfTrue => "jnz",
+            Opcode::JumpIfFalse => "jz",
+            Opcode::LessThan => "lt",
+            Opcode::Equals => "eq",
+            Opcode::AdjustBase => "arb",
+            Opcode::Halt => "halt",
+        }
+    }
+}
+
+impl Mode {
+    pub fn from_digit(d: Word) -> Option<Mode> {
+        match d {
+            0 => Some(Mode::Position),
+            1 => Some(Mode::Immediate),
+            2 => Some(Mode::Relative),
+            _ => None,
+        }
+    }
+
+    pub fn digit(self) -> Word {
+        match self {
+            Mode::Position => 0,
+            Mode::Immediate => 1,
+            Mode::Relative => 2,
+        }
+    }
+}
+
+impl Instruction {
+    pub fn decode(w: Word) -> Result<Instruction, DecodeError> {
+        Instruction::decode_for(w, Isa::default())
+    }
+
+    pub fn decode_for(w: Word, isa: Isa) -> Result<Instruction, DecodeError> {
+        let opcode = Opcode::from_word(w % 100).ok_or(DecodeError::InvalidOpcode(w))?;
+        if !isa.level.supports_opcode(opcode) {
+            return Err(DecodeError::UnsupportedOpcode(w, isa.level));
+        }
+
+        let mut modes = [Mode::Position; 3];
+
+        let mut digits = w / 100;
+        for (i, mode) in modes.iter_mut().enumerate().take(opcode.arity()) {
+            *mode = Mode::from_digit(digits % 10).ok_or(DecodeError::InvalidMode(w, i))?;
+            if !isa.level.supports_mode(*mode) {
+                return Err(DecodeError::UnsupportedMode(w, i, isa.level));
+            }
+            digits /= 10;
+        }
+
+        if opcode.writes() && modes[opcode.arity() - 1] == Mode::Immediate {
+            return Err(DecodeError::ImmediateDestination(w));
+        }
+        if isa.strict && digits != 0 {
+            return Err(DecodeError::TrailingModes(w));
+        }
+
+        Ok(Instruction { opcode, modes })
+    }
+
+    pub fn encode(&self) -> Word {
+        self.modes
+            .iter()
+            .take(self.opcode.arity())
+            .rev()
+            .fold(0, |acc, mode| acc * 10 + mode.digit())
+            * 100
+            + self.opcode.code()
+    }
+
+    pub fn size(&self) -> usize {
+        self.opcode.arity() + 1
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidOpcode(w) => write!(f, "invalid opcode in {}", w),
+            DecodeError::InvalidMode(w, i) => {
+                write!(f, "invalid mode for parameter {} in {}", i, w)
+            }
+            DecodeError::UnsupportedOpcode(w, level) => {
+                write!(f, "opcode of {} not available at level {:?}", w, level)
+            }
+            DecodeError::UnsupportedMode(w, i, level) => write!(
+                f,
+                "mode of parameter {} in {} not available at level {:?}",
+                i, w, level
+            ),
+            DecodeError::ImmediateDestination(w) => {
+                write!(f, "immediate mode destination in {}", w)
+            }
+            DecodeError::TrailingModes(w) => write!(f, "unused mode digits in {}", w),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_works() {
+        assert_eq!(
+            Instruction::decode(1002),
+            Ok(Instruction {
+                opcode: Opcode::Mul,
+                modes: [Mode::Position, Mode::Immediate, Mode::Position],
+            })
+        );
+        assert_eq!(
+            Instruction::decode(21_107).map(|i| i.modes),
+            Ok([Mode::Immediate, Mode::Immediate, Mode::Relative])
+        );
+        assert_eq!(Instruction::decode(42), Err(DecodeError::InvalidOpcode(42)));
+        assert_eq!(Instruction::decode(-1), Err(DecodeError::InvalidOpcode(-1)));
+        assert_eq!(
+            Instruction::decode(3001),
+            Err(DecodeError::InvalidMode(3001, 1))
+        );
+    }
+
+    #[test]
+    fn decode_checks_level() {
+        let day2 = Isa {
+            level: Level::Day2,
+            strict: false,
+        };
+        let day5 = Isa {
+            level: Level::Day5,
+            strict: false,
+        };
+
+        assert!(Instruction::decode_for(2, day2).is_ok());
+        assert_eq!(
+            Instruction::decode_for(3, day2),
+            Err(DecodeError::UnsupportedOpcode(3, Level::Day2))
+        );
+        assert_eq!(
+            Instruction::decode_for(1001, day2),
+            Err(DecodeError::UnsupportedMode(1001, 1, Level::Day2))
+        );
+        assert!(Instruction::decode_for(1001, day5).is_ok());
+        assert_eq!(
+            Instruction::decode_for(109, day5),
+            Err(DecodeError::UnsupportedOpcode(109, Level::Day5))
+        );
+        assert_eq!(
+            Instruction::decode_for(204, day5),
+            Err(DecodeError::UnsupportedMode(204, 0, Level::Day5))
+        );
+    }
+
+    #[test]
+    fn decode_checks_strict() {
+        let strict = Isa {
+            strict: true,
+            ..Isa::default()
+        };
+
+        assert!(Instruction::decode(10_104).is_ok());
+        assert_eq!(
+            Instruction::decode_for(10_104, strict),
+            Err(DecodeError::TrailingModes(10_104))
+        );
+        assert_eq!(
+            Instruction::decode_for(199, strict),
+            Err(DecodeError::TrailingModes(199))
+        );
+        assert_eq!(
+            Instruction::decode(11_101),
+            Err(DecodeError::ImmediateDestination(11_101))
+        );
+    }
+
+    #[test]
+    fn encode_roundtrips() {
+        for &w in &[1, 1002, 21_107, 109, 204, 1105, 99] {
+            assert_eq!(Instruction::decode(w).unwrap().encode(), w);
+        }
+    }
+}
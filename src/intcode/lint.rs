@@ -0,0 +1,211 @@
+use super::disasm;
+use super::isa::{DecodeError, Instruction, Isa, Level, Mode};
+use super::{Intcode, Word};
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Kind {
+    /// The word at a reachable address does not decode.
+    Decode(DecodeError),
+    /// A reachable instruction runs past the end of the program.
+    Truncated,
+    /// A jump to a constant target outside the program.
+    JumpOutOfRange(Word),
+    /// A position-mode store into the instruction at the given address.
+    SelfModifying(usize),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Finding {
+    pub addr: usize,
+    pub severity: Severity,
+    pub kind: Kind,
+}
+
+impl Kind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Kind::Decode(DecodeError::TrailingModes(_)) | Kind::SelfModifying(_) => {
+                Severity::Warning
+            }
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Decode(error) => write!(f, "{}", error),
+            Kind::Truncated => write!(f, "instruction runs past the end of the program"),
+            Kind::JumpOutOfRange(target) => write!(f, "jump to {}, outside the program", target),
+            Kind::SelfModifying(insn) => {
+                write!(f, "store into the instruction at {}", insn)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.addr, self.severity, self.kind)
+    }
+}
+
+pub fn lint(program: &str) -> Vec<Finding> {
+    lint_for(program, Level::Day9)
+}
+
+/// Checks the code reachable from address 0 against the instruction set of `level`.
+pub fn lint_for(program: &str, level: Level) -> Vec<Finding> {
+    let memory = Intcode::parse(program);
+    let strict = Isa {
+        level,
+        strict: true,
+    };
+
+    let mut findings = Vec::new();
+    let mut code = BTreeMap::new();
+    let mut work = vec![0];
+
+    let mut report = |addr, kind: Kind| {
+        findings.push(Finding {
+            addr,
+            severity: kind.severity(),
+            kind,
+        })
+    };
+
+    while let Some(addr) = work.pop() {
+        if code.contains_key(&addr) || addr >= memory.len() {
+            continue;
+        }
+
+        let insn = match Instruction::decode_for(memory[addr], strict) {
+            Ok(insn) => insn,
+            Err(error @ DecodeError::TrailingModes(_)) => {
+                report(addr, Kind::Decode(error));
+                Instruction::decode_for(
+                    memory[addr],
+                    Isa {
+                        strict: false,
+                        ..strict
+                    },
+                )
+                .unwrap()
+            }
+            Err(error) => {
+                report(addr, Kind::Decode(error));
+                continue;
+            }
+        };
+
+        if addr + insn.size() > memory.len() {
+            report(addr, Kind::Truncated);
+            continue;
+        }
+
+        code.insert(addr, insn);
+
+        if insn.opcode.is_jump() && insn.modes[1] == Mode::Immediate {
+            let target = memory[addr + 2];
+            if usize::try_from(target).map_or(true, |t| t >= memory.len()) {
+                report(addr, Kind::JumpOutOfRange(target));
+            }
+        }
+
+        work.extend(
+            disasm::successors(&memory, addr, &insn)
+                .into_iter()
+                .filter(|&next| next < memory.len()),
+        );
+    }
+
+    for (&addr, insn) in code.iter() {
+        let i = insn.opcode.arity().saturating_sub(1);
+        if !insn.opcode.writes() || insn.modes[i] != Mode::Position {
+            continue;
+        }
+
+        let dst = match usize::try_from(memory[addr + 1 + i]) {
+            Ok(dst) => dst,
+            Err(_) => continue,
+        };
+        if let Some((&target, t)) = code.range(..=dst).next_back() {
+            if dst < target + t.size() {
+                report(addr, Kind::SelfModifying(target));
+            }
+        }
+    }
+
+    findings.sort_by_key(|f| f.addr);
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hand_assembled() {
+        // 0: add [0], 1, [7] patches the jump at 6; 4: out 1 with a stray mode digit;
+        // 6: jz [20], 99 jumps out; 9: mul with an immediate destination
+        let findings = lint("1001,0,1,7,11104,1,1006,20,99,11102,2,2,3");
+
+        assert_eq!(
+            findings.iter().map(|f| f.to_string()).collect::<Vec<_>>(),
+            vec![
+                "0: warning: store into the instruction at 6",
+                "4: warning: unused mode digits in 11104",
+                "6: error: jump to 99, outside the program",
+                "9: error: immediate mode destination in 11102",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_and_truncated() {
+        let findings = lint("1105,1,5,99,0,301,1,2,3,1");
+
+        assert_eq!(
+            findings,
+            vec![Finding {
+                addr: 5,
+                severity: Severity::Error,
+                kind: Kind::Decode(DecodeError::InvalidMode(301, 0)),
+            }]
+        );
+        assert_eq!(lint("1,2,3")[0].kind, Kind::Truncated);
+        assert_eq!(
+            lint_for("109,1,99", Level::Day5)[0].kind,
+            Kind::Decode(DecodeError::UnsupportedOpcode(109, Level::Day5))
+        );
+    }
+
+    #[test]
+    fn puzzle_inputs() {
+        let day2 = lint(include_str!("../../res/2.txt"));
+        let day9 = lint(include_str!("../../res/9.txt"));
+
+        assert!(day2.iter().all(|f| f.severity == Severity::Warning));
+        assert!(day9.is_empty(), "{:?}", day9);
+    }
+}
@@ -1,6 +1,30 @@
+pub mod compiler;
+pub mod coverage;
+pub mod disasm;
+pub mod explorer;
+pub mod framebuffer;
+pub mod fuzz;
+pub mod gdb;
+mod history;
+pub mod isa;
+pub mod lint;
+pub mod optimizer;
+pub mod search;
+pub mod spec;
+pub mod symbolic;
+mod threaded;
+pub mod transpile;
+
+pub use history::History;
+pub use threaded::with_engine;
+
+use crate::parse::{self, ParseError};
+use crate::solution::Error;
+use isa::{DecodeError, Instruction, Isa, Level, Mode, Opcode};
+
 use std::collections::VecDeque;
 use std::convert::{TryFrom, TryInto};
-use std::str::FromStr;
+use std::fmt;
 
 pub type Word = i128;
 
@@ -10,6 +34,10 @@ pub struct Intcode {
     inputs: VecDeque<Word>,
     pc: usize,
     rb: usize,
+    isa: Isa,
+    engine: Engine,
+    cache: threaded::Cache,
+    history: Option<History>,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -19,34 +47,115 @@ enum Argument {
     Parameter(Word),
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Engine {
+    /// Decodes every instruction as it is executed.
+    Interpreter,
+    /// Decodes each instruction once and dispatches through the cached closure.
+    Threaded,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum StopCondition {
     Halt,
     NeedInput,
+    Fault(Fault),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Fault {
+    pub pc: usize,
+    pub error: DecodeError,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fault at {}: {}", self.pc, self.error)
+    }
+}
+
+/// Reports a run that stopped without the output a puzzle needs from it.
+pub fn missing_output() -> Error {
+    Error::unsolvable("the program stopped without the expected output")
 }
 
 impl Intcode {
     pub fn new(program: &str) -> Intcode {
+        Intcode::from_memory(Intcode::parse(program))
+    }
+
+    /// Like `new`, but reports a malformed program instead of panicking.
+    pub fn try_new(program: &str) -> Result<Intcode, ParseError> {
+        Intcode::try_parse(program).map(Intcode::from_memory)
+    }
+
+    fn from_memory(memory: Vec<Word>) -> Intcode {
         Intcode {
-            memory: Intcode::parse(program),
+            memory,
             inputs: VecDeque::new(),
             pc: 0,
             rb: 0,
+            isa: Isa::default(),
+            engine: threaded::default_engine(),
+            cache: threaded::Cache::default(),
+            history: None,
+        }
+    }
+
+    /// Resumes a machine from a memory image and register values, e.g. ones captured by
+    /// transpiled code that hit self-modifying code.
+    pub fn from_parts(memory: Vec<Word>, pc: usize, rb: usize) -> Intcode {
+        Intcode {
+            memory,
+            pc,
+            rb,
+            ..Intcode::from_memory(vec![])
         }
     }
 
+    pub fn engine(mut self, engine: Engine) -> Intcode {
+        self.engine = engine;
+        self
+    }
+
+    pub fn level(mut self, level: Level) -> Intcode {
+        self.isa.level = level;
+        self
+    }
+
+    pub fn strict(mut self) -> Intcode {
+        self.isa.strict = true;
+        self
+    }
+
     pub fn inputs(mut self, inputs: &[Word]) -> Intcode {
         self.inputs.extend(inputs);
         self
     }
 
     pub fn update(mut self, pos: usize, val: Word) -> Intcode {
+        if pos >= self.memory.len() {
+            self.memory.resize(pos + 1, 0);
+        }
         self.memory[pos] = val;
+        self.cache.clear();
         self
     }
 
     pub fn value(&self, pos: usize) -> Word {
-        self.memory[pos]
+        self.fetch(pos)
+    }
+
+    pub fn memory(&self) -> &[Word] {
+        &self.memory
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn rb(&self) -> usize {
+        self.rb
     }
 
     pub fn push_input(&mut self, input: Word) {
@@ -54,82 +163,99 @@ impl Intcode {
     }
 
     pub fn run(&mut self) -> (Vec<Word>, StopCondition) {
+        if self.engine == Engine::Threaded && self.history.is_none() {
+            return self.run_threaded();
+        }
+
         let mut outs = Vec::with_capacity(16);
 
         loop {
-            let op = self.memory[self.pc];
+            if let Some(sc) = self.step(&mut outs) {
+                return (outs, sc);
+            }
+        }
+    }
 
-            let opc = op % 100;
-            let mode = (op / 100).try_into().unwrap();
+    pub fn step(&mut self, outs: &mut Vec<Word>) -> Option<StopCondition> {
+        self.cache.clear();
 
-            match opc {
-                1 => {
-                    let ps = self.args(3, mode);
-                    let (a, b) = (self.rd(ps[0]), self.rd(ps[1]));
-                    self.wr(ps[2], a + b);
-                    self.pc += 4;
-                }
-                2 => {
-                    let ps = self.args(3, mode);
-                    let (a, b) = (self.rd(ps[0]), self.rd(ps[1]));
-                    self.wr(ps[2], a * b);
-                    self.pc += 4;
-                }
-                3 => {
-                    let ps = self.args(1, mode);
-                    if let Some(input) = self.inputs.pop_front() {
-                        self.wr(ps[0], input);
-                        self.pc += 2;
-                    } else {
-                        return (outs, StopCondition::NeedInput);
-                    }
-                }
-                4 => {
-                    let ps = self.args(1, mode);
-                    outs.push(self.rd(ps[0]));
-                    self.pc += 2;
-                }
-                5 => {
-                    let ps = self.args(2, mode);
-                    if self.rd(ps[0]) != 0 {
-                        self.pc = self.rd(ps[1]).try_into().unwrap();
-                    } else {
-                        self.pc += 3;
-                    }
-                }
-                6 => {
-                    let ps = self.args(2, mode);
-                    if self.rd(ps[0]) == 0 {
-                        self.pc = self.rd(ps[1]).try_into().unwrap();
-                    } else {
-                        self.pc += 3;
+        if let Some(history) = self.history.as_mut() {
+            history.begin(self.pc, self.rb, self.memory.len());
+        }
+
+        let sc = self.exec(outs);
+
+        if let Some(history) = self.history.as_mut() {
+            if sc.is_some() {
+                history.discard();
+            } else {
+                history.commit();
+            }
+        }
+
+        sc
+    }
+
+    fn exec(&mut self, outs: &mut Vec<Word>) -> Option<StopCondition> {
+        let insn = match Instruction::decode_for(self.fetch(self.pc), self.isa) {
+            Ok(insn) => insn,
+            Err(error) => return Some(StopCondition::Fault(Fault { pc: self.pc, error })),
+        };
+        let ps = self.args(&insn);
+
+        match insn.opcode {
+            Opcode::Add => {
+                let (a, b) = (self.rd(ps[0]), self.rd(ps[1]));
+                self.wr(ps[2], a + b);
+            }
+            Opcode::Mul => {
+                let (a, b) = (self.rd(ps[0]), self.rd(ps[1]));
+                self.wr(ps[2], a * b);
+            }
+            Opcode::Input => {
+                if let Some(input) = self.inputs.pop_front() {
+                    if let Some(history) = self.history.as_mut() {
+                        history.consume(input);
                     }
+                    self.wr(ps[0], input);
+                } else {
+                    return Some(StopCondition::NeedInput);
                 }
-                7 => {
-                    let ps = self.args(3, mode);
-                    let (a, b) = (self.rd(ps[0]), self.rd(ps[1]));
-                    self.wr(ps[2], (a < b).try_into().unwrap());
-                    self.pc += 4;
-                }
-                8 => {
-                    let ps = self.args(3, mode);
-                    let (a, b) = (self.rd(ps[0]), self.rd(ps[1]));
-                    self.wr(ps[2], (a == b).try_into().unwrap());
-                    self.pc += 4;
+            }
+            Opcode::Output => {
+                outs.push(self.rd(ps[0]));
+            }
+            Opcode::JumpIfTrue => {
+                if self.rd(ps[0]) != 0 {
+                    self.pc = self.rd(ps[1]).try_into().unwrap();
+                    return None;
                 }
-                9 => {
-                    let ps = self.args(1, mode);
-                    self.rb = (Word::try_from(self.rb).unwrap() + self.rd(ps[0]))
-                        .try_into()
-                        .unwrap();
-                    self.pc += 2;
+            }
+            Opcode::JumpIfFalse => {
+                if self.rd(ps[0]) == 0 {
+                    self.pc = self.rd(ps[1]).try_into().unwrap();
+                    return None;
                 }
-                99 => break,
-                _ => panic!("unexpected opcode: {}", self.memory[self.pc]),
             }
+            Opcode::LessThan => {
+                let (a, b) = (self.rd(ps[0]), self.rd(ps[1]));
+                self.wr(ps[2], (a < b).into());
+            }
+            Opcode::Equals => {
+                let (a, b) = (self.rd(ps[0]), self.rd(ps[1]));
+                self.wr(ps[2], (a == b).into());
+            }
+            Opcode::AdjustBase => {
+                self.rb = (Word::try_from(self.rb).unwrap() + self.rd(ps[0]))
+                    .try_into()
+                    .unwrap();
+            }
+            Opcode::Halt => return Some(StopCondition::Halt),
         }
 
-        (outs, StopCondition::Halt)
+        self.pc += insn.size();
+
+        None
     }
 
     fn rd(&mut self, arg: Argument) -> Word {
@@ -151,48 +277,153 @@ impl Intcode {
         }
     }
 
-    fn wr(&mut self, arg: Argument, w: Word) {
-        match arg {
-            Argument::Absolute(pos) => {
-                if pos >= self.memory.len() {
-                    self.memory.resize(pos + 1, 0);
-                }
-                self.memory[pos] = w;
-            }
+    fn wr(&mut self, arg: Argument, w: Word) -> usize {
+        let idx = match arg {
+            Argument::Absolute(pos) => pos,
             Argument::Relative(pos) => {
-                let idx = usize::try_from(isize::try_from(self.rb).unwrap() + pos).unwrap();
-                if idx >= self.memory.len() {
-                    self.memory.resize(idx + 1, 0);
-                }
-                self.memory[idx] = w;
+                usize::try_from(isize::try_from(self.rb).unwrap() + pos).unwrap()
+            }
+            Argument::Parameter(_) => {
+                unreachable!("immediate destinations are rejected by the decoder")
             }
-            Argument::Parameter(_) => panic!("cannot write in parameter mode"),
+        };
+
+        if idx >= self.memory.len() {
+            self.memory.resize(idx + 1, 0);
+        }
+        if let Some(history) = self.history.as_mut() {
+            history.write(idx, self.memory[idx]);
         }
+        self.memory[idx] = w;
+        idx
     }
 
-    fn args(&self, n: usize, mode: u64) -> Vec<Argument> {
-        (self.pc + 1..=self.pc + n)
-            .fold((mode, Vec::with_capacity(n)), |(mode, mut v), idx| {
-                let n = self.memory[idx];
+    /// Reads memory without growing it; everything past the end is zero.
+    fn fetch(&self, pos: usize) -> Word {
+        self.memory.get(pos).copied().unwrap_or(0)
+    }
 
-                let arg = match mode % 10 {
-                    0 => Argument::Absolute(n.try_into().unwrap()),
-                    1 => Argument::Parameter(n),
-                    2 => Argument::Relative(n.try_into().unwrap()),
-                    _ => panic!("invalid argument mode"),
-                };
+    fn args(&self, insn: &Instruction) -> [Argument; 3] {
+        let mut args = [Argument::Parameter(0); 3];
 
-                v.push(arg);
+        for (i, arg) in args.iter_mut().enumerate().take(insn.opcode.arity()) {
+            let n = self.fetch(self.pc + 1 + i);
 
-                (mode / 10, v)
-            })
-            .1
+            *arg = match insn.modes[i] {
+                Mode::Position => Argument::Absolute(n.try_into().unwrap()),
+                Mode::Immediate => Argument::Parameter(n),
+                Mode::Relative => Argument::Relative(n.try_into().unwrap()),
+            };
+        }
+
+        args
     }
 
     fn parse(program: &str) -> Vec<Word> {
+        Intcode::try_parse(program).unwrap_or_else(|e| panic!("invalid program: {}", e))
+    }
+
+    fn try_parse(program: &str) -> Result<Vec<Word>, ParseError> {
         program
+            .trim_end()
             .split(',')
-            .map(|s| Word::from_str(s).unwrap())
-            .collect::<Vec<_>>()
+            .map(|w| parse::number(program, w.trim()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_faults_on_later_instructions() {
+        let (outs, sc) = Intcode::new(include_str!("../../res/5.txt"))
+            .level(Level::Day2)
+            .run();
+        assert!(outs.is_empty());
+        assert_eq!(
+            sc,
+            StopCondition::Fault(Fault {
+                pc: 0,
+                error: DecodeError::UnsupportedOpcode(3, Level::Day2)
+            })
+        );
+
+        let (_, sc) = Intcode::new(include_str!("../../res/9.txt"))
+            .level(Level::Day5)
+            .inputs(&[1])
+            .run();
+        assert_eq!(
+            sc,
+            StopCondition::Fault(Fault {
+                pc: 15,
+                error: DecodeError::UnsupportedOpcode(109, Level::Day5)
+            })
+        );
+
+        let mut ic = Intcode::new(include_str!("../../res/2.txt"))
+            .level(Level::Day2)
+            .update(1, 12)
+            .update(2, 2);
+        assert_eq!(ic.run().1, StopCondition::Halt);
+        assert_eq!(ic.value(0), 5_534_943);
+
+        let (outs, sc) = Intcode::new(include_str!("../../res/5.txt"))
+            .level(Level::Day5)
+            .inputs(&[5])
+            .run();
+        assert_eq!((outs, sc), (vec![1_558_663], StopCondition::Halt));
+    }
+
+    #[test]
+    fn strict_rejects_unused_modes() {
+        assert_eq!(
+            Intcode::new("10104,7,99").run(),
+            (vec![7], StopCondition::Halt)
+        );
+
+        let mut ic = Intcode::new("104,7,10104,8,99").strict();
+        assert_eq!(
+            ic.run(),
+            (
+                vec![7],
+                StopCondition::Fault(Fault {
+                    pc: 2,
+                    error: DecodeError::TrailingModes(10_104)
+                })
+            )
+        );
+        assert_eq!(ic.pc(), 2);
+    }
+
+    #[test]
+    fn try_new_reports_bad_words() {
+        assert!(Intcode::try_new("104,7,99\n").is_ok());
+        assert_eq!(
+            Intcode::try_new("104, 7,x9,99").unwrap_err().to_string(),
+            "1:8: invalid number `x9`"
+        );
+    }
+
+    #[test]
+    fn memory_past_the_end_is_zero() {
+        // A lone `out` reads its operand and then opcode 0 past the end
+        for &engine in [Engine::Interpreter, Engine::Threaded].iter() {
+            let mut ic = Intcode::new("104").engine(engine);
+            assert_eq!(
+                ic.run(),
+                (
+                    vec![0],
+                    StopCondition::Fault(Fault {
+                        pc: 2,
+                        error: DecodeError::InvalidOpcode(0)
+                    })
+                )
+            );
+        }
+
+        let ic = Intcode::new("99").update(3, 7);
+        assert_eq!((ic.value(3), ic.value(10)), (7, 0));
     }
 }
@@ -0,0 +1,444 @@
+use super::disasm::{self, Listing};
+use super::isa::{Instruction, Mode, Opcode};
+use super::{Intcode, StopCondition, Word};
+
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Rewrite {
+    /// Arithmetic on two immediates replaced by a move of the result.
+    Fold { addr: usize, value: Word },
+    /// A branch on an immediate condition, which is either always or never taken.
+    ConstantBranch { addr: usize, taken: bool },
+    /// A jump retargeted past a chain of unconditional jumps.
+    Thread { addr: usize, from: usize, to: usize },
+    /// Unreachable words zeroed out, or trimmed when they end the program.
+    Unreachable { start: usize, end: usize },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub rewrites: Vec<Rewrite>,
+    /// Reachable instructions left alone because the program reads or writes their words.
+    pub protected: Vec<usize>,
+    /// Why unreachable code was kept, if it was.
+    pub kept_unreachable: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub inputs: Vec<Word>,
+    pub expected: (Vec<Word>, StopCondition),
+    pub actual: (Vec<Word>, StopCondition),
+}
+
+impl fmt::Display for Rewrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Rewrite::Fold { addr, value } => write!(f, "{:>6}: folded to {}", addr, value),
+            Rewrite::ConstantBranch { addr, taken: true } => {
+                write!(f, "{:>6}: branch always taken", addr)
+            }
+            Rewrite::ConstantBranch { addr, taken: false } => {
+                write!(f, "{:>6}: branch never taken", addr)
+            }
+            Rewrite::Thread { addr, from, to } => {
+                write!(f, "{:>6}: jump threaded {} -> {}", addr, from, to)
+            }
+            Rewrite::Unreachable { start, end } => {
+                write!(f, "{:>6}: removed {} unreachable words", start, end - start)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for rewrite in self.rewrites.iter() {
+            writeln!(f, "{}", rewrite)?;
+        }
+        if !self.protected.is_empty() {
+            let addrs = self
+                .protected
+                .iter()
+                .map(|addr| addr.to_string())
+                .collect::<Vec<_>>();
+            writeln!(f, "protected: {}", addrs.join(", "))?;
+        }
+        if let Some(reason) = self.kept_unreachable {
+            writeln!(f, "unreachable code kept: {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rewrites `program` into an equivalent one that executes fewer instructions. Addresses are
+/// preserved, so data and computed jumps stay valid.
+///
+/// Instructions whose words are accessed through position-mode operands are never touched.
+/// Programs with computed jumps or relative-mode writes are left as they are, since code
+/// reached only through the former, or the targets of the latter, may patch any instruction.
+pub fn optimize(program: &str) -> (String, Report) {
+    let mut memory = Intcode::parse(program);
+    let mut report = Report::default();
+
+    let listing = disasm::reachable(&memory, vec![0]);
+    let touched = touched(&memory, &listing);
+    let relative_writes = listing
+        .code
+        .values()
+        .any(|insn| insn.opcode.writes() && insn.modes[insn.opcode.arity() - 1] == Mode::Relative);
+
+    let safe = listing
+        .code
+        .iter()
+        .filter(|_| listing.indirect.is_empty() && !relative_writes)
+        .filter(|&(&addr, insn)| {
+            let clean = touched.range(addr..addr + insn.size()).next().is_none();
+            if !clean {
+                report.protected.push(addr);
+            }
+            clean
+        })
+        .map(|(&addr, &insn)| (addr, insn))
+        .collect::<Vec<_>>();
+
+    for &(addr, insn) in safe.iter() {
+        if let Some(rewrite) = fold(&mut memory, addr, insn) {
+            report.rewrites.push(rewrite);
+        }
+    }
+
+    let safe = safe
+        .into_iter()
+        .map(|(addr, _)| (addr, disasm::decode_at(&memory, addr).unwrap()))
+        .collect::<Vec<_>>();
+    let is_safe = |addr| safe.binary_search_by_key(&addr, |&(a, _)| a).is_ok();
+
+    for &(addr, insn) in safe.iter() {
+        if !insn.opcode.is_jump() || insn.modes[1] != Mode::Immediate {
+            continue;
+        }
+
+        let from = memory[addr + 2];
+        let to = thread(&memory, is_safe, from);
+        if to != from {
+            memory[addr + 2] = to;
+            report.rewrites.push(Rewrite::Thread {
+                addr,
+                from: usize::try_from(from).unwrap(),
+                to: usize::try_from(to).unwrap(),
+            });
+        }
+    }
+
+    let incomplete = listing.code.iter().any(|(&addr, insn)| {
+        disasm::successors(&memory, addr, insn)
+            .iter()
+            .any(|next| !listing.code.contains_key(next))
+    });
+
+    report.kept_unreachable = if !listing.indirect.is_empty() {
+        Some("computed jumps")
+    } else if incomplete || !report.protected.is_empty() {
+        Some("self-modifying code")
+    } else if listing
+        .code
+        .values()
+        .any(|insn| insn.modes.contains(&Mode::Relative))
+    {
+        Some("relative-mode accesses")
+    } else {
+        None
+    };
+
+    if report.kept_unreachable.is_none() {
+        remove_unreachable(&mut memory, &touched, &mut report);
+    }
+
+    let program = memory
+        .iter()
+        .map(|w| w.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    (program, report)
+}
+
+/// Runs both programs on every input sequence and reports the first that behaves differently.
+pub fn verify(original: &str, optimized: &str, inputs: &[Vec<Word>]) -> Result<(), Box<Mismatch>> {
+    for inputs in inputs {
+        let expected = Intcode::new(original).inputs(inputs).run();
+        let actual = Intcode::new(optimized).inputs(inputs).run();
+
+        if expected != actual {
+            return Err(Box::new(Mismatch {
+                inputs: inputs.clone(),
+                expected,
+                actual,
+            }));
+        }
+    }
+
+    Ok(())
+}
+
+/// Addresses accessed through position-mode operands of reachable instructions.
+fn touched(memory: &[Word], listing: &Listing) -> BTreeSet<usize> {
+    let mut touched = BTreeSet::new();
+
+    for (&addr, insn) in listing.code.iter() {
+        for i in 0..insn.opcode.arity() {
+            if insn.modes[i] == Mode::Position {
+                if let Ok(pos) = usize::try_from(memory[addr + 1 + i]) {
+                    touched.insert(pos);
+                }
+            }
+        }
+    }
+
+    touched
+}
+
+fn fold(memory: &mut [Word], addr: usize, insn: Instruction) -> Option<Rewrite> {
+    let (a, b) = (memory[addr + 1], memory[addr + 2]);
+
+    if insn.modes[0] != Mode::Immediate
+        || (!insn.opcode.is_jump() && insn.modes[1] != Mode::Immediate)
+    {
+        return None;
+    }
+
+    let value = match insn.opcode {
+        Opcode::Add if b == 0 => return None,
+        Opcode::Add => a + b,
+        Opcode::Mul => a * b,
+        Opcode::LessThan => (a < b).into(),
+        Opcode::Equals => (a == b).into(),
+        Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+            let taken = (a != 0) == (insn.opcode == Opcode::JumpIfTrue);
+            if taken {
+                if (insn.opcode, a) == (Opcode::JumpIfTrue, 1) {
+                    return None;
+                }
+                memory[addr] = Instruction {
+                    opcode: Opcode::JumpIfTrue,
+                    ..insn
+                }
+                .encode();
+                memory[addr + 1] = 1;
+            }
+            return Some(Rewrite::ConstantBranch { addr, taken });
+        }
+        _ => return None,
+    };
+
+    memory[addr] = Instruction {
+        opcode: Opcode::Add,
+        ..insn
+    }
+    .encode();
+    memory[addr + 1] = value;
+    memory[addr + 2] = 0;
+
+    Some(Rewrite::Fold { addr, value })
+}
+
+/// Follows unconditional jumps and never-taken branches from `target` to the first address
+/// that does real work.
+fn thread<F>(memory: &[Word], is_safe: F, target: Word) -> Word
+where
+    F: Fn(usize) -> bool,
+{
+    let mut seen = BTreeSet::new();
+    let mut target = target;
+
+    while let Ok(addr) = usize::try_from(target) {
+        if !seen.insert(addr) || !is_safe(addr) {
+            break;
+        }
+
+        let insn = match disasm::decode_at(memory, addr) {
+            Some(insn) if insn.opcode.is_jump() && insn.modes[0] == Mode::Immediate => insn,
+            _ => break,
+        };
+
+        let taken = (memory[addr + 1] != 0) == (insn.opcode == Opcode::JumpIfTrue);
+        if !taken {
+            target = (addr + insn.size()) as Word;
+        } else if insn.modes[1] == Mode::Immediate {
+            target = memory[addr + 2];
+        } else {
+            break;
+        }
+    }
+
+    target
+}
+
+fn remove_unreachable(memory: &mut Vec<Word>, touched: &BTreeSet<usize>, report: &mut Report) {
+    let listing = disasm::reachable(memory, vec![0]);
+
+    let mut live = vec![false; memory.len()];
+    for (&addr, insn) in listing.code.iter() {
+        for flag in live[addr..addr + insn.size()].iter_mut() {
+            *flag = true;
+        }
+    }
+    for &pos in touched.range(..memory.len()) {
+        live[pos] = true;
+    }
+
+    let mut addr = 0;
+    while addr < memory.len() {
+        if live[addr] {
+            addr += 1;
+            continue;
+        }
+
+        let start = addr;
+        while addr < memory.len() && !live[addr] {
+            addr += 1;
+        }
+
+        if memory[start..addr].iter().all(|&w| w == 0) {
+            continue;
+        }
+
+        for w in memory[start..addr].iter_mut() {
+            *w = 0;
+        }
+        report
+            .rewrites
+            .push(Rewrite::Unreachable { start, end: addr });
+    }
+
+    while memory.len() > 1 && memory.last() == Some(&0) && !live[memory.len() - 1] {
+        memory.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::compiler;
+
+    #[test]
+    fn folds_threads_and_removes() {
+        // 0: mul 6, 7 -> [20]; 4: jnz 1, 8; 7: halt; 8: jz 0, 11; 11: jnz 1, 16
+        // 14: data; 16: out [20]; 18: halt; 19: data; 20: result
+        let program = "1102,6,7,20,1105,1,8,99,1106,0,11,1105,1,16,7,7,4,20,99,5,0";
+        let (optimized, report) = optimize(program);
+
+        assert_eq!(
+            optimized,
+            "1101,42,0,20,1105,1,16,0,0,0,0,0,0,0,0,0,4,20,99,0,0"
+        );
+        assert_eq!(
+            report.rewrites,
+            vec![
+                Rewrite::Fold { addr: 0, value: 42 },
+                Rewrite::ConstantBranch {
+                    addr: 8,
+                    taken: true
+                },
+                Rewrite::Thread {
+                    addr: 4,
+                    from: 8,
+                    to: 16
+                },
+                Rewrite::Thread {
+                    addr: 8,
+                    from: 11,
+                    to: 16
+                },
+                Rewrite::Unreachable { start: 7, end: 16 },
+                Rewrite::Unreachable { start: 19, end: 20 },
+            ]
+        );
+        assert_eq!(verify(program, &optimized, &[vec![]]), Ok(()));
+    }
+
+    #[test]
+    fn self_modifying_code_is_protected() {
+        let day5 = include_str!("../../res/5.txt");
+        let (optimized, report) = optimize(day5);
+
+        assert_eq!(report.kept_unreachable, Some("self-modifying code"));
+        assert_eq!(verify(day5, &optimized, &[vec![1], vec![5]]), Ok(()));
+    }
+
+    #[test]
+    fn compiled_programs() {
+        let src = "
+            fn main() {
+                let n = input();
+                while 1 {
+                    if n == 0 { return; }
+                    if 0 { output(-1); }
+                    output(n * (2 + 3));
+                    n = n - 1;
+                }
+            }
+        ";
+        let program = compiler::compile(src).unwrap();
+        let (optimized, report) = optimize(&program);
+
+        assert_eq!(report.rewrites, vec![]);
+        assert_eq!(report.kept_unreachable, Some("computed jumps"));
+        assert_eq!(
+            verify(&program, &optimized, &[vec![0], vec![1], vec![7]]),
+            Ok(())
+        );
+
+        let day9 = include_str!("../../res/9.txt");
+        let (optimized, _) = optimize(day9);
+        assert_eq!(verify(day9, &optimized, &[vec![1]]), Ok(()));
+    }
+
+    #[test]
+    fn relative_writes_disable_rewrites() {
+        // 0: add 9, 0 -> [rb+6], patching the second operand of 4: mul 6, 7 -> [12]
+        // 8: out [12]; 10: halt
+        let program = "21101,9,0,6,1102,6,7,12,4,12,99,0,0";
+        let (optimized, report) = optimize(program);
+
+        assert_eq!(report.rewrites, vec![]);
+        assert_eq!(report.kept_unreachable, Some("relative-mode accesses"));
+        assert_eq!(verify(program, &optimized, &[vec![]]), Ok(()));
+        assert_eq!(
+            Intcode::new(&optimized).run(),
+            (vec![54], StopCondition::Halt)
+        );
+    }
+
+    #[test]
+    fn computed_jumps_disable_rewrites() {
+        // 0: jnz [30], [31]; 3: mul 6, 7 -> [40]; 7: out [40]; 9: halt
+        // 10: add 100, 0 -> [4], reached only through the computed jump; 14: jnz 1, 3
+        let mut memory = [0; 41];
+        let code = [
+            5, 30, 31, 1102, 6, 7, 40, 4, 40, 99, 1101, 100, 0, 4, 1105, 1, 3,
+        ];
+        memory[..code.len()].copy_from_slice(&code);
+        memory[30] = 1;
+        memory[31] = 10;
+        let program = memory
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let (optimized, report) = optimize(&program);
+
+        assert_eq!(optimized, program);
+        assert_eq!(report.kept_unreachable, Some("computed jumps"));
+        assert_eq!(verify(&program, &optimized, &[vec![]]), Ok(()));
+        assert_eq!(
+            Intcode::new(&optimized).run(),
+            (vec![700], StopCondition::Halt)
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use super::{Intcode, StopCondition, Word};
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Strategy {
+    BreadthFirst,
+    DepthFirst,
+}
+
+/// Searches the states a machine reaches by feeding it one choice of inputs at a time.
+///
+/// `choices` lists the inputs to try given the outputs of the step that led to a state, and
+/// `goal` is checked against the outputs of every step. States with the same memory, `pc`
+/// and `rb` are only expanded once, and machines that halt or fault are dead ends.
+#[derive(Debug, Clone)]
+pub struct Search<C, G> {
+    choices: C,
+    goal: G,
+    strategy: Strategy,
+    limit: Option<usize>,
+}
+
+impl<C, G> Search<C, G>
+where
+    C: Fn(&[Word]) -> Vec<Vec<Word>>,
+    G: Fn(&[Word]) -> bool,
+{
+    pub fn new(choices: C, goal: G) -> Search<C, G> {
+        Search {
+            choices,
+            goal,
+            strategy: Strategy::BreadthFirst,
+            limit: None,
+        }
+    }
+
+    pub fn strategy(mut self, strategy: Strategy) -> Search<C, G> {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Gives up after expanding this many states.
+    pub fn limit(mut self, limit: usize) -> Search<C, G> {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Returns the inputs that lead from `ic` to a goal, in the order they are fed. The
+    /// breadth-first strategy finds a path with the fewest choices.
+    pub fn run(&self, mut ic: Intcode) -> Option<Vec<Word>> {
+        let (outs, sc) = ic.run();
+        if (self.goal)(&outs) {
+            return Some(vec![]);
+        }
+
+        let mut seen = HashSet::new();
+        let mut work = VecDeque::new();
+        let mut expanded = 0;
+
+        seen.insert(fingerprint(&ic));
+        if sc == StopCondition::NeedInput {
+            work.push_back((ic, outs, vec![]));
+        }
+
+        while let Some((ic, outs, path)) = match self.strategy {
+            Strategy::BreadthFirst => work.pop_front(),
+            Strategy::DepthFirst => work.pop_back(),
+        } {
+            if self.limit == Some(expanded) {
+                return None;
+            }
+            expanded += 1;
+
+            for choice in (self.choices)(&outs) {
+                let mut next = ic.clone();
+                for &input in choice.iter() {
+                    next.push_input(input);
+                }
+
+                let (outs, sc) = next.run();
+                let mut path = path.clone();
+                path.extend_from_slice(&choice);
+
+                if (self.goal)(&outs) {
+                    return Some(path);
+                }
+                if sc == StopCondition::NeedInput && seen.insert(fingerprint(&next)) {
+                    work.push_back((next, outs, path));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+fn fingerprint(ic: &Intcode) -> u64 {
+    // Memory only grows with zeroes, so trailing zeroes don't tell states apart.
+    let len = ic.memory.iter().rposition(|&w| w != 0).map_or(0, |i| i + 1);
+
+    let mut hasher = DefaultHasher::new();
+    ic.memory[..len].hash(&mut hasher);
+    ic.pc.hash(&mut hasher);
+    ic.rb.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(_: &[Word]) -> Vec<Vec<Word>> {
+        (1..=4).map(|d| vec![d]).collect()
+    }
+
+    #[test]
+    fn shortest_path_to_oxygen() {
+        let ic = Intcode::new(include_str!("../../res/15.txt"));
+        let path = Search::new(moves, |outs| outs == [2]).run(ic.clone());
+
+        assert_eq!(path.map(|p| p.len()), Some(366));
+
+        let path = Search::new(moves, |outs| outs == [2])
+            .strategy(Strategy::DepthFirst)
+            .run(ic.clone())
+            .unwrap();
+        let mut replay = ic.inputs(&path);
+        assert_eq!(replay.run().0.last(), Some(&2));
+    }
+
+    #[test]
+    fn limits_and_dead_ends() {
+        // Reads a number and halts, printing whether it was 7
+        let ic = Intcode::new("3,9,1008,9,7,10,4,10,99");
+        let choices = |_: &[Word]| (0..10).map(|n| vec![n]).collect();
+
+        assert_eq!(
+            Search::new(choices, |o| o == [1]).run(ic.clone()),
+            Some(vec![7])
+        );
+        assert_eq!(Search::new(choices, |o| o == [2]).run(ic.clone()), None);
+        assert_eq!(
+            Search::new(moves, |o| o == [2])
+                .limit(10)
+                .run(Intcode::new(include_str!("../../res/15.txt"))),
+            None
+        );
+    }
+}
@@ -0,0 +1,540 @@
+use super::{Intcode, StopCondition, Word};
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Steps a case may run for unless it sets its own `steps`.
+const DEFAULT_STEPS: usize = 100_000_000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Stop {
+    Halt,
+    Input,
+    Fault,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    Words(Vec<Word>),
+    Ascii(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    pub name: String,
+    pub path: PathBuf,
+    pub line: usize,
+    pub program: PathBuf,
+    pub inputs: Vec<Word>,
+    pub patches: Vec<(usize, Word)>,
+    pub output: Option<Output>,
+    pub last: Option<Word>,
+    pub memory: Vec<(usize, Word)>,
+    pub stop: Option<Stop>,
+    pub steps: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecError {
+    pub path: PathBuf,
+    pub line: usize,
+    pub msg: String,
+}
+
+/// The result of running one case: an empty `diff` means it passed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub name: String,
+    pub diff: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub outcomes: Vec<Outcome>,
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.path.display(), self.line, self.msg)
+    }
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        self.diff.is_empty()
+    }
+}
+
+impl Report {
+    pub fn passed(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.passed()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.outcomes.len() - self.passed()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for outcome in self.outcomes.iter() {
+            if outcome.passed() {
+                writeln!(f, "PASS {}", outcome.name)?;
+            } else {
+                writeln!(f, "FAIL {}", outcome.name)?;
+                for line in outcome.diff.iter() {
+                    writeln!(f, "    {}", line)?;
+                }
+            }
+        }
+        write!(f, "{} passed, {} failed", self.passed(), self.failed())
+    }
+}
+
+/// Parses the cases in `src`; program paths are resolved against the directory of `path`.
+///
+/// A spec file holds one or more cases, each starting with a `[name]` header followed by
+/// `key = value` lines; `#` starts a comment.
+///
+/// ```text
+/// [day 2, first part]
+/// program = ../res/2.txt       # relative to the spec file
+/// patch[1] = 12                # memory writes before the run
+/// patch[2] = 2
+/// memory[0] = 5534943          # expected memory after the run
+/// stop = halt                  # halt, input or fault
+///
+/// [springdroid]
+/// program = ../res/21.txt
+/// input = ascii "NOT A J\nWALK\n"   # repeated inputs are appended
+/// last = 19355790              # expected last output
+/// output = 1, 2, 3             # expected outputs, numbers or ascii "..."
+/// steps = 1000000              # fails with a timeout past this many steps
+/// ```
+pub fn parse(src: &str, path: &Path) -> Result<Vec<Case>, SpecError> {
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut cases: Vec<Case> = Vec::new();
+
+    for (i, raw) in src.lines().enumerate() {
+        let err = |msg: String| SpecError {
+            path: path.to_path_buf(),
+            line: i + 1,
+            msg,
+        };
+
+        let line = strip_comment(raw).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            cases.push(Case {
+                name: line[1..line.len() - 1].trim().to_string(),
+                path: path.to_path_buf(),
+                line: i + 1,
+                program: PathBuf::new(),
+                inputs: Vec::new(),
+                patches: Vec::new(),
+                output: None,
+                last: None,
+                memory: Vec::new(),
+                stop: None,
+                steps: DEFAULT_STEPS,
+            });
+            continue;
+        }
+
+        let case = cases
+            .last_mut()
+            .ok_or_else(|| err("expected a [case] header".to_string()))?;
+
+        let eq = line
+            .find('=')
+            .ok_or_else(|| err(format!("expected `key = value`, found `{}`", line)))?;
+        let (key, value) = (line[..eq].trim(), line[eq + 1..].trim());
+
+        match key {
+            "program" => case.program = base.join(value),
+            "input" => case
+                .inputs
+                .extend(parse_output(value).map_err(err)?.words()),
+            "output" => case.output = Some(parse_output(value).map_err(err)?),
+            "last" => case.last = Some(parse_word(value).map_err(err)?),
+            "steps" => {
+                case.steps = value
+                    .parse()
+                    .map_err(|_| err(format!("invalid step count `{}`", value)))?
+            }
+            "stop" => {
+                case.stop = Some(match value {
+                    "halt" => Stop::Halt,
+                    "input" => Stop::Input,
+                    "fault" => Stop::Fault,
+                    _ => return Err(err(format!("unknown stop condition `{}`", value))),
+                })
+            }
+            _ => {
+                let (name, idx) =
+                    parse_indexed(key).ok_or_else(|| err(format!("unknown key `{}`", key)))?;
+                let w = parse_word(value).map_err(err)?;
+                match name {
+                    "memory" => case.memory.push((idx, w)),
+                    "patch" => case.patches.push((idx, w)),
+                    _ => return Err(err(format!("unknown key `{}`", key))),
+                }
+            }
+        }
+    }
+
+    for case in cases.iter() {
+        if case.program.as_os_str().is_empty() {
+            return Err(SpecError {
+                path: path.to_path_buf(),
+                line: case.line,
+                msg: format!("case `{}` has no program", case.name),
+            });
+        }
+    }
+
+    Ok(cases)
+}
+
+pub fn load(path: &Path) -> Result<Vec<Case>, SpecError> {
+    let src = fs::read_to_string(path).map_err(|e| SpecError {
+        path: path.to_path_buf(),
+        line: 0,
+        msg: e.to_string(),
+    })?;
+    parse(&src, path)
+}
+
+/// Runs every case of every `.spec` file in `dir`, in file name order.
+pub fn run_dir(dir: &Path) -> Result<Report, SpecError> {
+    let io_err = |e: std::io::Error| SpecError {
+        path: dir.to_path_buf(),
+        line: 0,
+        msg: e.to_string(),
+    };
+
+    let mut paths = fs::read_dir(dir)
+        .map_err(io_err)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(io_err)?;
+    paths.retain(|p| p.extension().is_some_and(|ext| ext == "spec"));
+    paths.sort();
+
+    let mut report = Report::default();
+    for path in paths {
+        for case in load(&path)? {
+            report.outcomes.push(case.run()?);
+        }
+    }
+
+    Ok(report)
+}
+
+impl Case {
+    pub fn run(&self) -> Result<Outcome, SpecError> {
+        let program = fs::read_to_string(&self.program).map_err(|e| SpecError {
+            path: self.program.clone(),
+            line: 0,
+            msg: e.to_string(),
+        })?;
+
+        let mut ic = Intcode::try_new(program.trim())
+            .map_err(|e| SpecError {
+                path: self.program.clone(),
+                line: e.line,
+                msg: e.msg,
+            })?
+            .inputs(&self.inputs);
+
+        for &(idx, w) in self.patches.iter() {
+            if idx >= ic.memory.len() {
+                return Err(SpecError {
+                    path: self.path.clone(),
+                    line: self.line,
+                    msg: format!(
+                        "case `{}` patches {}, past the end of its {}-word program",
+                        self.name,
+                        idx,
+                        ic.memory.len()
+                    ),
+                });
+            }
+            ic = ic.update(idx, w);
+        }
+
+        let mut outs = Vec::new();
+        let sc = match (0..self.steps).find_map(|_| ic.step(&mut outs)) {
+            Some(sc) => sc,
+            None => {
+                return Ok(Outcome {
+                    name: self.name.clone(),
+                    diff: vec![format!("timeout: still running after {} steps", self.steps)],
+                })
+            }
+        };
+        let mut diff = Vec::new();
+
+        if let Some(stop) = self.stop {
+            let actual = match sc {
+                StopCondition::Halt => Stop::Halt,
+                StopCondition::NeedInput => Stop::Input,
+                StopCondition::Fault(_) => Stop::Fault,
+            };
+            if actual != stop {
+                diff.push(format!("stop: expected {:?}, got {:?}", stop, sc));
+            }
+        }
+
+        match &self.output {
+            Some(Output::Words(expected)) => diff_words(expected, &outs, &mut diff),
+            Some(Output::Ascii(expected)) => diff_ascii(expected, &outs, &mut diff),
+            None => (),
+        }
+
+        if let Some(last) = self.last {
+            match outs.last() {
+                Some(&actual) if actual == last => (),
+                Some(&actual) => {
+                    diff.push(format!("last output: expected {}, got {}", last, actual))
+                }
+                None => diff.push(format!("last output: expected {}, got nothing", last)),
+            }
+        }
+
+        for &(idx, expected) in self.memory.iter() {
+            let actual = if idx < ic.memory.len() {
+                ic.value(idx)
+            } else {
+                0
+            };
+            if actual != expected {
+                diff.push(format!(
+                    "memory[{}]: expected {}, got {}",
+                    idx, expected, actual
+                ));
+            }
+        }
+
+        Ok(Outcome {
+            name: self.name.clone(),
+            diff,
+        })
+    }
+}
+
+impl Output {
+    fn words(self) -> Vec<Word> {
+        match self {
+            Output::Words(words) => words,
+            Output::Ascii(s) => s.bytes().map(Word::from).collect(),
+        }
+    }
+}
+
+fn diff_words(expected: &[Word], actual: &[Word], diff: &mut Vec<String>) {
+    if expected.len() != actual.len() {
+        diff.push(format!(
+            "output: expected {} values, got {}",
+            expected.len(),
+            actual.len()
+        ));
+    }
+
+    let first = expected.iter().zip(actual).position(|(e, a)| e != a);
+    if let Some(i) = first {
+        diff.push(format!(
+            "output[{}]: expected {}, got {}",
+            i, expected[i], actual[i]
+        ));
+    }
+}
+
+fn diff_ascii(expected: &str, actual: &[Word], diff: &mut Vec<String>) {
+    let actual = actual
+        .iter()
+        .map(|&w| match u8::try_from(w) {
+            Ok(b) if b.is_ascii() => (b as char).to_string(),
+            _ => format!("<{}>", w),
+        })
+        .collect::<String>();
+
+    if actual == expected {
+        return;
+    }
+
+    let (exp, act) = (
+        expected.lines().collect::<Vec<_>>(),
+        actual.lines().collect::<Vec<_>>(),
+    );
+    for i in 0..exp.len().max(act.len()) {
+        match (exp.get(i), act.get(i)) {
+            (Some(e), Some(a)) if e == a => diff.push(format!("  {}", e)),
+            (e, a) => {
+                if let Some(e) = e {
+                    diff.push(format!("- {}", e));
+                }
+                if let Some(a) = a {
+                    diff.push(format!("+ {}", a));
+                }
+            }
+        }
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => (),
+        }
+    }
+
+    line
+}
+
+fn parse_word(s: &str) -> Result<Word, String> {
+    Word::from_str(s).map_err(|_| format!("invalid number `{}`", s))
+}
+
+fn parse_indexed(key: &str) -> Option<(&str, usize)> {
+    let open = key.find('[')?;
+    let idx = key.strip_suffix(']')?[open + 1..].trim().parse().ok()?;
+    Some((key[..open].trim(), idx))
+}
+
+fn parse_output(value: &str) -> Result<Output, String> {
+    match value.strip_prefix("ascii") {
+        Some(rest) => parse_string(rest.trim()).map(Output::Ascii),
+        None if value.is_empty() => Ok(Output::Words(vec![])),
+        None => value
+            .split(',')
+            .map(|s| parse_word(s.trim()))
+            .collect::<Result<_, _>>()
+            .map(Output::Words),
+    }
+}
+
+fn parse_string(s: &str) -> Result<String, String> {
+    let inner = s
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(|| format!("expected a quoted string, found `{}`", s))?;
+
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('\\') => out.push('\\'),
+            Some('"') => out.push('"'),
+            other => return Err(format!("invalid escape `\\{}`", other.unwrap_or(' '))),
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_errors() {
+        let path = Path::new("specs/x.spec");
+        let err = |src| parse(src, path).unwrap_err().to_string();
+
+        assert_eq!(
+            err("program = a"),
+            "specs/x.spec:1: expected a [case] header"
+        );
+        assert_eq!(
+            err("[a]\nprogram = a\n\nstop = done"),
+            "specs/x.spec:4: unknown stop condition `done`"
+        );
+        assert_eq!(
+            err("[a]\ninput = ascii \"x\\q\""),
+            "specs/x.spec:2: invalid escape `\\q`"
+        );
+        assert_eq!(
+            err("[a]\nlast = 1"),
+            "specs/x.spec:1: case `a` has no program"
+        );
+        assert_eq!(
+            err("[a]\nsteps = many"),
+            "specs/x.spec:2: invalid step count `many`"
+        );
+    }
+
+    #[test]
+    fn run_errors_and_timeouts() {
+        let run = |src| parse(src, Path::new("specs/x.spec")).unwrap()[0].run();
+
+        assert_eq!(
+            run("[a]\nprogram = programs/eq8.int\npatch[100] = 1")
+                .unwrap_err()
+                .to_string(),
+            "specs/x.spec:1: case `a` patches 100, past the end of its 11-word program"
+        );
+        assert_eq!(
+            run("[a]\nprogram = vm.spec").unwrap_err().path,
+            Path::new("specs/vm.spec")
+        );
+        assert_eq!(
+            run("[a]\nprogram = programs/loop.int\nsteps = 1000")
+                .unwrap()
+                .diff,
+            vec!["timeout: still running after 1000 steps"]
+        );
+    }
+
+    #[test]
+    fn failures_are_diffed() {
+        let src = "
+            [wrong]
+            program = ../res/5.txt
+            input = 1
+            output = 0, 0, 0
+            last = 42
+            memory[0] = 4
+            stop = input
+        ";
+        let cases = parse(src, Path::new("specs/x.spec")).unwrap();
+        let outcome = cases[0].run().unwrap();
+
+        assert_eq!(
+            outcome.diff,
+            vec![
+                "stop: expected Input, got Halt",
+                "output: expected 3 values, got 10",
+                "last output: expected 42, got 15097178",
+                "memory[0]: expected 4, got 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn specs_directory() {
+        let report = run_dir(Path::new("specs")).unwrap();
+
+        assert!(report.outcomes.len() >= 5);
+        assert_eq!(report.failed(), 0, "\n{}", report);
+    }
+}
@@ -0,0 +1,469 @@
+use super::isa::{Instruction, Mode, Opcode};
+use super::{Intcode, Word};
+
+use itertools::Itertools;
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::ops::RangeInclusive;
+
+const MAX_STEPS: usize = 1_000_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Const(Word),
+    Var(usize),
+    /// A value loaded through an address that depends on a variable.
+    Opaque,
+    Add(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Lt(Box<Expr>, Box<Expr>),
+    Eq(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Affine {
+    pub coeffs: Vec<Word>,
+    pub constant: Word,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Target {
+    Memory(usize),
+    Output(usize),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SymbolicError {
+    SymbolicCode(usize),
+    SymbolicBranch(usize),
+    SymbolicAddress(usize),
+    InvalidInstruction(usize),
+    NeedInput(usize),
+    MissingOutput(usize),
+    StepLimit,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbolic {
+    memory: Vec<Expr>,
+    inputs: VecDeque<Expr>,
+    vars: usize,
+    pc: usize,
+    rb: usize,
+}
+
+impl Expr {
+    fn add(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Const(a), Expr::Const(b)) => Expr::Const(a + b),
+            (Expr::Const(0), e) | (e, Expr::Const(0)) => e,
+            (a, b) => Expr::Add(Box::new(a), Box::new(b)),
+        }
+    }
+
+    fn mul(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Const(a), Expr::Const(b)) => Expr::Const(a * b),
+            (Expr::Const(0), _) | (_, Expr::Const(0)) => Expr::Const(0),
+            (Expr::Const(1), e) | (e, Expr::Const(1)) => e,
+            (a, b) => Expr::Mul(Box::new(a), Box::new(b)),
+        }
+    }
+
+    fn lt(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Const(a), Expr::Const(b)) => Expr::Const((a < b).into()),
+            (a, b) => Expr::Lt(Box::new(a), Box::new(b)),
+        }
+    }
+
+    fn eq(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Const(a), Expr::Const(b)) => Expr::Const((a == b).into()),
+            (a, b) => Expr::Eq(Box::new(a), Box::new(b)),
+        }
+    }
+
+    pub fn eval(&self, vars: &[Word]) -> Option<Word> {
+        Some(match self {
+            Expr::Const(c) => *c,
+            Expr::Var(i) => vars[*i],
+            Expr::Opaque => return None,
+            Expr::Add(a, b) => a.eval(vars)? + b.eval(vars)?,
+            Expr::Mul(a, b) => a.eval(vars)? * b.eval(vars)?,
+            Expr::Lt(a, b) => (a.eval(vars)? < b.eval(vars)?).into(),
+            Expr::Eq(a, b) => (a.eval(vars)? == b.eval(vars)?).into(),
+        })
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        match self {
+            Expr::Const(_) | Expr::Var(_) => false,
+            Expr::Opaque => true,
+            Expr::Add(a, b) | Expr::Mul(a, b) | Expr::Lt(a, b) | Expr::Eq(a, b) => {
+                a.is_opaque() || b.is_opaque()
+            }
+        }
+    }
+
+    /// Returns the expression as `c0 * v0 + c1 * v1 + ... + k`, if it is affine.
+    pub fn affine(&self, vars: usize) -> Option<Affine> {
+        match self {
+            Expr::Const(c) => Some(Affine {
+                coeffs: vec![0; vars],
+                constant: *c,
+            }),
+            Expr::Var(i) => {
+                let mut coeffs = vec![0; vars];
+                coeffs[*i] = 1;
+                Some(Affine {
+                    coeffs,
+                    constant: 0,
+                })
+            }
+            Expr::Add(a, b) => {
+                let (a, b) = (a.affine(vars)?, b.affine(vars)?);
+                Some(Affine {
+                    coeffs: a.coeffs.iter().zip(&b.coeffs).map(|(x, y)| x + y).collect(),
+                    constant: a.constant + b.constant,
+                })
+            }
+            Expr::Mul(a, b) => {
+                let (a, b) = (a.affine(vars)?, b.affine(vars)?);
+                let (k, e) = if a.is_constant() {
+                    (a.constant, b)
+                } else if b.is_constant() {
+                    (b.constant, a)
+                } else {
+                    return None;
+                };
+                Some(Affine {
+                    coeffs: e.coeffs.iter().map(|c| c * k).collect(),
+                    constant: e.constant * k,
+                })
+            }
+            Expr::Opaque | Expr::Lt(_, _) | Expr::Eq(_, _) => None,
+        }
+    }
+}
+
+impl Affine {
+    pub fn is_constant(&self) -> bool {
+        self.coeffs.iter().all(|&c| c == 0)
+    }
+}
+
+impl Symbolic {
+    pub fn new(program: &str) -> Symbolic {
+        Symbolic::from_memory(&Intcode::parse(program))
+    }
+
+    pub fn from_memory(memory: &[Word]) -> Symbolic {
+        Symbolic {
+            memory: memory.iter().copied().map(Expr::Const).collect(),
+            inputs: VecDeque::new(),
+            vars: 0,
+            pc: 0,
+            rb: 0,
+        }
+    }
+
+    pub fn var_at(mut self, pos: usize) -> Symbolic {
+        if pos >= self.memory.len() {
+            self.memory.resize(pos + 1, Expr::Const(0));
+        }
+        self.memory[pos] = Expr::Var(self.vars);
+        self.vars += 1;
+        self
+    }
+
+    pub fn input(mut self, w: Word) -> Symbolic {
+        self.inputs.push_back(Expr::Const(w));
+        self
+    }
+
+    pub fn input_var(mut self) -> Symbolic {
+        self.inputs.push_back(Expr::Var(self.vars));
+        self.vars += 1;
+        self
+    }
+
+    pub fn vars(&self) -> usize {
+        self.vars
+    }
+
+    pub fn eval(mut self, target: Target) -> Result<Expr, SymbolicError> {
+        let mut outs = Vec::new();
+
+        for _ in 0..MAX_STEPS {
+            let pc = self.pc;
+
+            let insn = match self.load(pc) {
+                Expr::Const(w) => {
+                    Instruction::decode(w).map_err(|_| SymbolicError::InvalidInstruction(pc))?
+                }
+                _ => return Err(SymbolicError::SymbolicCode(pc)),
+            };
+
+            let ps = (0..insn.opcode.arity())
+                .map(|i| (insn.modes[i], self.load(pc + 1 + i)))
+                .collect::<Vec<_>>();
+
+            match insn.opcode {
+                Opcode::Add | Opcode::Mul | Opcode::LessThan | Opcode::Equals => {
+                    let (a, b) = (self.rd(&ps[0]), self.rd(&ps[1]));
+                    let r = match insn.opcode {
+                        Opcode::Add => Expr::add(a, b),
+                        Opcode::Mul => Expr::mul(a, b),
+                        Opcode::LessThan => Expr::lt(a, b),
+                        _ => Expr::eq(a, b),
+                    };
+                    self.wr(&ps[2], r)?;
+                }
+                Opcode::Input => {
+                    let input = self
+                        .inputs
+                        .pop_front()
+                        .ok_or(SymbolicError::NeedInput(pc))?;
+                    self.wr(&ps[0], input)?;
+                }
+                Opcode::Output => outs.push(self.rd(&ps[0])),
+                Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                    let cond = match self.rd(&ps[0]) {
+                        Expr::Const(c) => c != 0,
+                        _ => return Err(SymbolicError::SymbolicBranch(pc)),
+                    };
+                    if cond == (insn.opcode == Opcode::JumpIfTrue) {
+                        self.pc = match self.rd(&ps[1]) {
+                            Expr::Const(t) => usize::try_from(t)
+                                .map_err(|_| SymbolicError::InvalidInstruction(pc))?,
+                            _ => return Err(SymbolicError::SymbolicBranch(pc)),
+                        };
+                        continue;
+                    }
+                }
+                Opcode::AdjustBase => match self.rd(&ps[0]) {
+                    Expr::Const(d) => {
+                        self.rb = usize::try_from(Word::try_from(self.rb).unwrap() + d)
+                            .map_err(|_| SymbolicError::InvalidInstruction(pc))?;
+                    }
+                    _ => return Err(SymbolicError::SymbolicAddress(pc)),
+                },
+                Opcode::Halt => {
+                    return match target {
+                        Target::Memory(pos) => Ok(self.load(pos)),
+                        Target::Output(n) => outs
+                            .into_iter()
+                            .nth(n)
+                            .ok_or(SymbolicError::MissingOutput(n)),
+                    };
+                }
+            }
+
+            self.pc += insn.size();
+        }
+
+        Err(SymbolicError::StepLimit)
+    }
+
+    fn load(&self, pos: usize) -> Expr {
+        self.memory.get(pos).cloned().unwrap_or(Expr::Const(0))
+    }
+
+    fn address(&self, mode: Mode, e: &Expr) -> Option<usize> {
+        match (mode, e) {
+            (Mode::Position, Expr::Const(p)) => usize::try_from(*p).ok(),
+            (Mode::Relative, Expr::Const(p)) => {
+                usize::try_from(Word::try_from(self.rb).unwrap() + p).ok()
+            }
+            _ => None,
+        }
+    }
+
+    fn rd(&self, (mode, e): &(Mode, Expr)) -> Expr {
+        if *mode == Mode::Immediate {
+            return e.clone();
+        }
+        match self.address(*mode, e) {
+            Some(pos) => self.load(pos),
+            None => Expr::Opaque,
+        }
+    }
+
+    fn wr(&mut self, (mode, e): &(Mode, Expr), value: Expr) -> Result<(), SymbolicError> {
+        let pos = self
+            .address(*mode, e)
+            .ok_or(SymbolicError::SymbolicAddress(self.pc))?;
+
+        if pos >= self.memory.len() {
+            self.memory.resize(pos + 1, Expr::Const(0));
+        }
+        self.memory[pos] = value;
+        Ok(())
+    }
+}
+
+/// Finds every assignment of `domains` for which `expr` evaluates to `target`.
+///
+/// Affine expressions are solved for one of their variables directly, other expressions are
+/// evaluated over the whole domain. Returns `None` if the expression cannot be evaluated.
+pub fn solve(
+    expr: &Expr,
+    target: Word,
+    domains: &[RangeInclusive<Word>],
+) -> Option<Vec<Vec<Word>>> {
+    if expr.is_opaque() {
+        return None;
+    }
+
+    let affine = match expr.affine(domains.len()) {
+        Some(affine) => affine,
+        None => {
+            return Some(
+                assignments(domains)
+                    .filter(|vs| expr.eval(vs) == Some(target))
+                    .collect(),
+            )
+        }
+    };
+
+    let pivot = match affine.coeffs.iter().rposition(|&c| c != 0) {
+        Some(pivot) => pivot,
+        None if affine.constant == target => return Some(assignments(domains).collect()),
+        None => return Some(vec![]),
+    };
+
+    let mut others = domains.to_vec();
+    others.remove(pivot);
+
+    let mut solutions = assignments(&others)
+        .filter_map(|mut vs| {
+            let rest = vs
+                .iter()
+                .enumerate()
+                .map(|(i, v)| affine.coeffs[if i < pivot { i } else { i + 1 }] * v)
+                .sum::<Word>();
+            let rem = target - affine.constant - rest;
+
+            if rem % affine.coeffs[pivot] != 0 {
+                return None;
+            }
+
+            let v = rem / affine.coeffs[pivot];
+            if !domains[pivot].contains(&v) {
+                return None;
+            }
+
+            vs.insert(pivot, v);
+            Some(vs)
+        })
+        .collect::<Vec<_>>();
+
+    solutions.sort();
+    Some(solutions)
+}
+
+/// Finds every assignment of the memory cells in `vars` for which `memory` halts with `target`
+/// stored at `pos`, falling back to running the program on each assignment when it cannot be
+/// solved symbolically.
+pub fn solve_memory(
+    memory: &[Word],
+    vars: &[(usize, RangeInclusive<Word>)],
+    pos: usize,
+    target: Word,
+) -> Vec<Vec<Word>> {
+    let domains = vars.iter().map(|(_, d)| d.clone()).collect::<Vec<_>>();
+
+    let expr = vars
+        .iter()
+        .fold(Symbolic::from_memory(memory), |sym, &(p, _)| sym.var_at(p))
+        .eval(Target::Memory(pos));
+
+    if let Some(solutions) = expr.ok().and_then(|e| solve(&e, target, &domains)) {
+        return solutions;
+    }
+
+    let ic = Intcode::from_parts(memory.to_vec(), 0, 0);
+
+    assignments(&domains)
+        .filter(|vs| {
+            let mut ic = vars
+                .iter()
+                .zip(vs)
+                .fold(ic.clone(), |ic, (&(p, _), &v)| ic.update(p, v));
+            ic.run();
+            ic.value(pos) == target
+        })
+        .collect()
+}
+
+fn assignments(domains: &[RangeInclusive<Word>]) -> Box<dyn Iterator<Item = Vec<Word>> + '_> {
+    if domains.is_empty() {
+        Box::new(std::iter::once(vec![]))
+    } else {
+        Box::new(domains.iter().cloned().multi_cartesian_product())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_2_is_affine() {
+        let expr = Symbolic::new(include_str!("../../res/2.txt"))
+            .var_at(1)
+            .var_at(2)
+            .eval(Target::Memory(0))
+            .unwrap();
+
+        let affine = expr.affine(2).unwrap();
+        assert_eq!(affine.coeffs[1], 1);
+        assert_eq!(expr.eval(&[12, 2]), Some(5_534_943));
+
+        assert_eq!(
+            solve(&expr, 19_690_720, &[0..=99, 0..=99]),
+            Some(vec![vec![76, 3]])
+        );
+    }
+
+    #[test]
+    fn non_linear_expressions_are_enumerated() {
+        // out(in * in + 1)
+        let expr = Symbolic::new("3,15,3,16,2,15,16,17,1001,17,1,17,4,17,99")
+            .input_var()
+            .input_var()
+            .eval(Target::Output(0))
+            .unwrap();
+
+        assert_eq!(expr.affine(2), None);
+        assert_eq!(
+            solve(&expr, 13, &[-4..=4, -4..=4]),
+            Some(vec![vec![-4, -3], vec![-3, -4], vec![3, 4], vec![4, 3]])
+        );
+    }
+
+    #[test]
+    fn code_past_the_end_reads_as_zero() {
+        assert_eq!(
+            Symbolic::new("1,0,0").var_at(1).eval(Target::Memory(0)),
+            Err(SymbolicError::InvalidInstruction(4))
+        );
+    }
+
+    #[test]
+    fn symbolic_branches_fall_back_to_search() {
+        // mem[0] = 7 if mem[1] == 3, else mem[0] = 1
+        let program = "1108,0,3,20,1006,20,15,1101,0,7,0,1105,1,19,0,1101,0,1,0,99,0";
+
+        assert_eq!(
+            Symbolic::new(program).var_at(1).eval(Target::Memory(0)),
+            Err(SymbolicError::SymbolicBranch(4))
+        );
+        assert_eq!(
+            solve_memory(&Intcode::parse(program), &[(1, 0..=9)], 0, 7),
+            vec![vec![3]]
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use super::isa::{Instruction, Opcode};
+use super::{Argument, Engine, Fault, Intcode, StopCondition, Word};
+
+use std::cell::Cell;
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::mem;
+use std::rc::Rc;
+
+type Op = Rc<dyn Fn(&mut Intcode, &mut Vec<Word>) -> Flow>;
+
+enum Flow {
+    /// Continue with the next instruction, reporting the address written to, if any.
+    Next(Option<usize>),
+    Stop(StopCondition),
+}
+
+#[derive(Clone, Default)]
+pub(super) struct Cache {
+    ops: Vec<Option<(usize, Op)>>,
+}
+
+thread_local! {
+    static DEFAULT_ENGINE: Cell<Engine> = const { Cell::new(Engine::Interpreter) };
+}
+
+pub(super) fn default_engine() -> Engine {
+    DEFAULT_ENGINE.with(|e| e.get())
+}
+
+/// Runs `f` with every `Intcode` created on this thread defaulting to `engine`.
+pub fn with_engine<F, T>(engine: Engine, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    struct Restore(Engine);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            DEFAULT_ENGINE.with(|e| e.set(self.0));
+        }
+    }
+
+    let _restore = Restore(DEFAULT_ENGINE.with(|e| e.replace(engine)));
+    f()
+}
+
+impl Cache {
+    pub(super) fn clear(&mut self) {
+        if !self.ops.is_empty() {
+            self.ops.clear();
+        }
+    }
+
+    fn get(&self, pc: usize) -> Option<&Op> {
+        self.ops.get(pc)?.as_ref().map(|(_, op)| op)
+    }
+
+    fn insert(&mut self, pc: usize, size: usize, op: Op) {
+        if pc >= self.ops.len() {
+            self.ops.resize(pc + 1, None);
+        }
+        self.ops[pc] = Some((size, op));
+    }
+
+    /// Drops every cached instruction that spans `addr`.
+    fn invalidate(&mut self, addr: usize) {
+        let end = self.ops.len().min(addr + 1);
+
+        for start in addr.saturating_sub(3)..end {
+            if let Some((size, _)) = self.ops[start] {
+                if start + size > addr {
+                    self.ops[start] = None;
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cache({} instructions)",
+            self.ops.iter().filter(|op| op.is_some()).count()
+        )
+    }
+}
+
+impl Intcode {
+    pub(super) fn run_threaded(&mut self) -> (Vec<Word>, StopCondition) {
+        let mut outs = Vec::with_capacity(16);
+        let mut cache = mem::take(&mut self.cache);
+
+        let sc = loop {
+            let flow = match cache.get(self.pc) {
+                Some(op) => op(self, &mut outs),
+                None => match self.compile() {
+                    Ok((size, op)) => {
+                        cache.insert(self.pc, size, op.clone());
+                        op(self, &mut outs)
+                    }
+                    Err(sc) => break sc,
+                },
+            };
+
+            match flow {
+                Flow::Next(None) => (),
+                Flow::Next(Some(addr)) => cache.invalidate(addr),
+                Flow::Stop(sc) => break sc,
+            }
+        };
+
+        self.cache = cache;
+        (outs, sc)
+    }
+
+    fn compile(&self) -> Result<(usize, Op), StopCondition> {
+        let insn = Instruction::decode_for(self.fetch(self.pc), self.isa)
+            .map_err(|error| StopCondition::Fault(Fault { pc: self.pc, error }))?;
+        let ps = self.args(&insn);
+        let next = self.pc + insn.size();
+
+        Ok((insn.size(), compile(insn.opcode, ps, next)))
+    }
+}
+
+fn compile(opcode: Opcode, ps: [Argument; 3], next: usize) -> Op {
+    match opcode {
+        Opcode::Add => Rc::new(move |ic, _| {
+            let v = ic.rd(ps[0]) + ic.rd(ps[1]);
+            let addr = ic.wr(ps[2], v);
+            ic.pc = next;
+            Flow::Next(Some(addr))
+        }),
+        Opcode::Mul => Rc::new(move |ic, _| {
+            let v = ic.rd(ps[0]) * ic.rd(ps[1]);
+            let addr = ic.wr(ps[2], v);
+            ic.pc = next;
+            Flow::Next(Some(addr))
+        }),
+        Opcode::Input => Rc::new(move |ic, _| match ic.inputs.pop_front() {
+            Some(input) => {
+                let addr = ic.wr(ps[0], input);
+                ic.pc = next;
+                Flow::Next(Some(addr))
+            }
+            None => Flow::Stop(StopCondition::NeedInput),
+        }),
+        Opcode::Output => Rc::new(move |ic, outs| {
+            outs.push(ic.rd(ps[0]));
+            ic.pc = next;
+            Flow::Next(None)
+        }),
+        Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+            let when = opcode == Opcode::JumpIfTrue;
+            Rc::new(move |ic, _| {
+                if (ic.rd(ps[0]) != 0) == when {
+                    ic.pc = ic.rd(ps[1]).try_into().unwrap();
+                } else {
+                    ic.pc = next;
+                }
+                Flow::Next(None)
+            })
+        }
+        Opcode::LessThan => Rc::new(move |ic, _| {
+            let v = (ic.rd(ps[0]) < ic.rd(ps[1])).into();
+            let addr = ic.wr(ps[2], v);
+            ic.pc = next;
+            Flow::Next(Some(addr))
+        }),
+        Opcode::Equals => Rc::new(move |ic, _| {
+            let v = (ic.rd(ps[0]) == ic.rd(ps[1])).into();
+            let addr = ic.wr(ps[2], v);
+            ic.pc = next;
+            Flow::Next(Some(addr))
+        }),
+        Opcode::AdjustBase => Rc::new(move |ic, _| {
+            ic.rb = (Word::try_from(ic.rb).unwrap() + ic.rd(ps[0]))
+                .try_into()
+                .unwrap();
+            ic.pc = next;
+            Flow::Next(None)
+        }),
+        Opcode::Halt => Rc::new(|_, _| Flow::Stop(StopCondition::Halt)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    /// Runs `f` on both engines, checking that they agree, and returns the result.
+    fn threaded<F, T>(f: F) -> T
+    where
+        F: Fn() -> T,
+        T: PartialEq + fmt::Debug,
+    {
+        let interpreted = with_engine(Engine::Interpreter, &f);
+        let threaded = with_engine(Engine::Threaded, &f);
+        assert_eq!(threaded, interpreted);
+        threaded
+    }
+
+    #[test]
+    fn engine_is_restored_after_a_panic() {
+        let panicked = std::panic::catch_unwind(|| {
+            with_engine(Engine::Threaded, || panic!("boom"));
+        });
+
+        assert!(panicked.is_err());
+        assert_eq!(default_engine(), Engine::Interpreter);
+    }
+
+    #[test]
+    fn self_modifying_code_is_redecoded() {
+        // Counts down from 3, bumping the mode of the `out` at 11 on every iteration.
+        let program = "1001,20,-1,20,1006,20,19,1001,11,100,11,4,20,1105,1,0,0,0,0,99,3";
+
+        let reference = Intcode::new(program).run();
+        let mut ic = Intcode::new(program).engine(Engine::Threaded);
+
+        assert_eq!(reference, (vec![20, 1], StopCondition::Halt));
+        assert_eq!(ic.run(), reference);
+    }
+
+    #[test]
+    fn engines_agree_on_faults() {
+        let mut interpreted = Intcode::new("104,1,3,5,42").inputs(&[1]);
+        let mut threaded = interpreted.clone().engine(Engine::Threaded);
+
+        assert_eq!(interpreted.run(), threaded.run());
+        assert_eq!(interpreted.pc(), threaded.pc());
+    }
+
+    #[test]
+    fn days_2_to_9() {
+        let (d2, d5) = (
+            include_str!("../../res/2.txt"),
+            include_str!("../../res/5.txt"),
+        );
+        let (d7, d9) = (
+            include_str!("../../res/7.txt"),
+            include_str!("../../res/9.txt"),
+        );
+
+        assert_eq!(threaded(|| day2::part_1(d2)), Ok(5_534_943));
+        assert_eq!(threaded(|| day5::part_1(d5)), Ok(15_097_178));
+        assert_eq!(threaded(|| day5::part_2(d5)), Ok(1_558_663));
+        assert_eq!(threaded(|| day7::part_1(d7)), Ok(118_936));
+        assert_eq!(threaded(|| day7::part_2(d7)), Ok(57_660_948));
+        assert_eq!(threaded(|| day9::part_1(d9)), Ok(3_345_854_957));
+        assert_eq!(threaded(|| day9::part_2(d9)), Ok(68938));
+    }
+
+    #[test]
+    fn days_11_to_15() {
+        let (d11, d13) = (
+            include_str!("../../res/11.txt"),
+            include_str!("../../res/13.txt"),
+        );
+        let d15 = include_str!("../../res/15.txt");
+
+        assert_eq!(threaded(|| day11::part_1(d11)), Ok(2255));
+        assert_eq!(threaded(|| day11::part_2(d11)), Ok("BCKFPCRA".to_string()));
+        assert_eq!(threaded(|| day13::part_1(d13)), Ok(213));
+        assert_eq!(threaded(|| day13::part_2(d13)), Ok(11441));
+        assert_eq!(threaded(|| day15::part_1(d15)), Ok(366));
+        assert_eq!(threaded(|| day15::part_2(d15)), Ok(384));
+    }
+
+    #[test]
+    fn days_17_to_21() {
+        let (d17, d19) = (
+            include_str!("../../res/17.txt"),
+            include_str!("../../res/19.txt"),
+        );
+        let d21 = include_str!("../../res/21.txt");
+
+        assert_eq!(threaded(|| day17::part_1(d17)), Ok(10632));
+        assert_eq!(threaded(|| day17::part_2(d17)), Ok(1_356_191));
+        assert_eq!(threaded(|| day19::part_1(d19)), Ok(126));
+        assert_eq!(threaded(|| day19::part_2(d19)), Ok(11_351_625));
+        assert_eq!(threaded(|| day21::part_1(d21)), Ok(19_355_790));
+        assert_eq!(threaded(|| day21::part_2(d21)), Ok(1_140_920_822));
+    }
+}
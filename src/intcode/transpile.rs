@@ -0,0 +1,432 @@
+use super::disasm;
+use super::isa::{Instruction, Mode, Opcode};
+use super::Word;
+
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Turns an Intcode program into Rust source for a machine type with the same interface as
+/// `Intcode`: `new`, `inputs`, `push_input` and `run`.
+///
+/// The generated code is a module body, meant to be `include!`d into a module of its own. Each
+/// statically known instruction becomes an arm of a `match` on `pc`; anything else, including
+/// every run after a write into the code, is handed over to the interpreter.
+#[derive(Debug, Clone)]
+pub struct Transpiler {
+    name: String,
+    crate_path: String,
+}
+
+impl Transpiler {
+    pub fn new(name: &str) -> Transpiler {
+        Transpiler {
+            name: name.to_string(),
+            crate_path: "crate".to_string(),
+        }
+    }
+
+    /// Sets the path of this crate as seen from the generated code, `crate` by default.
+    pub fn crate_path(mut self, path: &str) -> Transpiler {
+        self.crate_path = path.to_string();
+        self
+    }
+
+    pub fn transpile(&self, program: &str) -> String {
+        let memory = program
+            .trim()
+            .split(',')
+            .map(|s| Word::from_str(s.trim()).unwrap())
+            .collect::<Vec<_>>();
+        let code = Code::new(&memory);
+
+        let mut out = String::new();
+        self.header(&mut out, &memory, &code);
+
+        for &(addr, insn) in code.insns.iter() {
+            writeln!(out, "                {} => {{", addr).unwrap();
+            for line in code.arm(&memory, addr, &insn) {
+                writeln!(out, "                    {}", line).unwrap();
+            }
+            writeln!(out, "                }}").unwrap();
+        }
+
+        out.push_str(FOOTER);
+        out
+    }
+
+    fn header(&self, out: &mut String, memory: &[Word], code: &Code) {
+        let words = memory
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut spans: Vec<(usize, usize)> = Vec::new();
+        for &(addr, insn) in code.insns.iter() {
+            for idx in addr..addr + insn.size() {
+                if code.patched.contains(&idx) {
+                    continue;
+                }
+                match spans.last_mut() {
+                    Some(last) if idx <= last.1 + 1 => last.1 = last.1.max(idx),
+                    _ => spans.push((idx, idx)),
+                }
+            }
+        }
+        let spans = spans
+            .iter()
+            .map(|(start, end)| format!("{}..={}", start, end))
+            .collect::<Vec<_>>();
+
+        let header = HEADER
+            .replace("$crate", &self.crate_path)
+            .replace("$name", &self.name)
+            .replace("$program", &words)
+            .replace(
+                "$code",
+                &if spans.is_empty() {
+                    "false".to_string()
+                } else {
+                    format!("matches!(idx, {})", spans.join(" | "))
+                },
+            );
+        out.push_str(&header);
+    }
+}
+
+struct Code {
+    insns: Vec<(usize, Instruction)>,
+    /// Operand words the program overwrites, which are read from memory at run time.
+    patched: BTreeSet<usize>,
+}
+
+impl Code {
+    /// Finds the instructions to compile: those reachable from 0 and, if the program jumps
+    /// through computed targets, from every constant it stores that points just past a jump,
+    /// which could be a return address. Instructions whose opcode the program overwrites are
+    /// left to the interpreter.
+    fn new(memory: &[Word]) -> Code {
+        let mut entries = BTreeSet::new();
+        entries.insert(0);
+
+        loop {
+            let listing = disasm::reachable(memory, entries.iter().cloned());
+            let before = entries.len();
+
+            if !listing.indirect.is_empty() {
+                let returns = listing
+                    .code
+                    .iter()
+                    .filter(|(_, insn)| insn.opcode.is_jump())
+                    .map(|(&addr, insn)| addr + insn.size())
+                    .collect::<BTreeSet<_>>();
+
+                for (&addr, insn) in listing.code.iter() {
+                    if let Some(target) = stored_constant(memory, addr, insn) {
+                        if returns.contains(&target) {
+                            entries.insert(target);
+                        }
+                    }
+                }
+            }
+
+            if entries.len() != before {
+                continue;
+            }
+
+            let mut written = BTreeSet::new();
+            for (&addr, insn) in listing.code.iter() {
+                let i = insn.opcode.arity().saturating_sub(1);
+                if insn.opcode.writes() && insn.modes[i] == Mode::Position {
+                    if let Ok(idx) = usize::try_from(memory[addr + 1 + i]) {
+                        written.insert(idx);
+                    }
+                }
+            }
+
+            let insns = listing
+                .code
+                .into_iter()
+                .filter(|&(addr, insn)| {
+                    !written.contains(&addr)
+                        && (0..insn.opcode.arity()).all(|i| {
+                            insn.modes[i] != Mode::Position
+                                || memory[addr + 1 + i] >= 0
+                                || written.contains(&(addr + 1 + i))
+                        })
+                })
+                .collect::<Vec<_>>();
+
+            let patched = insns
+                .iter()
+                .flat_map(|&(addr, insn)| addr + 1..addr + insn.size())
+                .filter(|idx| written.contains(idx))
+                .collect();
+
+            return Code { insns, patched };
+        }
+    }
+
+    fn operand(&self, memory: &[Word], addr: usize, insn: &Instruction, i: usize) -> String {
+        let idx = addr + 1 + i;
+        let n = memory[idx];
+
+        match (insn.modes[i], self.patched.contains(&idx)) {
+            (Mode::Position, false) => format!("self.rd({})", n),
+            (Mode::Immediate, false) if n < 0 => format!("({})", n),
+            (Mode::Immediate, false) => format!("{}", n),
+            (Mode::Immediate, true) => format!("self.rd({})", idx),
+            _ => format!("self.rd({})", self.destination(memory, addr, insn, i)),
+        }
+    }
+
+    fn destination(&self, memory: &[Word], addr: usize, insn: &Instruction, i: usize) -> String {
+        let idx = addr + 1 + i;
+        let n = memory[idx];
+
+        match (insn.modes[i], self.patched.contains(&idx)) {
+            (Mode::Relative, false) => format!("self.rel({})", n),
+            (Mode::Relative, true) => {
+                format!("self.rel(isize::try_from(self.rd({})).unwrap())", idx)
+            }
+            (_, false) => format!("{}", n),
+            (_, true) => format!("usize::try_from(self.rd({})).unwrap()", idx),
+        }
+    }
+
+    fn arm(&self, memory: &[Word], addr: usize, insn: &Instruction) -> Vec<String> {
+        let next = addr + insn.size();
+        let op = |i| self.operand(memory, addr, insn, i);
+        let dst = |i| self.destination(memory, addr, insn, i);
+
+        match insn.opcode {
+            Opcode::Add | Opcode::Mul | Opcode::LessThan | Opcode::Equals => {
+                let v = match insn.opcode {
+                    Opcode::Add => format!("{} + {}", op(0), op(1)),
+                    Opcode::Mul => format!("{} * {}", op(0), op(1)),
+                    Opcode::LessThan => format!("Word::from({} < {})", op(0), op(1)),
+                    _ => format!("Word::from({} == {})", op(0), op(1)),
+                };
+                vec![
+                    format!("let v = {};", v),
+                    format!("self.wr({}, v);", dst(2)),
+                    format!("self.pc = {};", next),
+                ]
+            }
+            Opcode::Input => vec![
+                "match self.inputs.pop_front() {".to_string(),
+                format!("    Some(v) => self.wr({}, v),", dst(0)),
+                "    None => return (outs, StopCondition::NeedInput),".to_string(),
+                "}".to_string(),
+                format!("self.pc = {};", next),
+            ],
+            Opcode::Output => vec![
+                format!("outs.push({});", op(0)),
+                format!("self.pc = {};", next),
+            ],
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => vec![format!(
+                "self.pc = if {} {} 0 {{ usize::try_from({}).unwrap() }} else {{ {} }};",
+                op(0),
+                if insn.opcode == Opcode::JumpIfTrue {
+                    "!="
+                } else {
+                    "=="
+                },
+                op(1),
+                next
+            )],
+            Opcode::AdjustBase => vec![
+                format!(
+                    "self.rb = usize::try_from(Word::try_from(self.rb).unwrap() + {}).unwrap();",
+                    op(0)
+                ),
+                format!("self.pc = {};", next),
+            ],
+            Opcode::Halt => vec!["return (outs, StopCondition::Halt);".to_string()],
+        }
+    }
+}
+
+fn stored_constant(memory: &[Word], addr: usize, insn: &Instruction) -> Option<usize> {
+    let (a, b) = (memory[addr + 1], memory[addr + 2]);
+
+    if insn.modes[0] != Mode::Immediate || insn.modes[1] != Mode::Immediate {
+        return None;
+    }
+
+    match insn.opcode {
+        Opcode::Add => usize::try_from(a.checked_add(b)?).ok(),
+        Opcode::Mul => usize::try_from(a.checked_mul(b)?).ok(),
+        _ => None,
+    }
+}
+
+const HEADER: &str = r#"// Generated from an Intcode program, do not edit.
+
+use $crate::intcode::{Intcode, StopCondition, Word};
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+
+const PROGRAM: &[Word] = &[$program];
+
+#[derive(Debug, Clone)]
+pub struct $name {
+    memory: Vec<Word>,
+    inputs: VecDeque<Word>,
+    pc: usize,
+    rb: usize,
+    dirty: bool,
+    fallback: Option<Intcode>,
+}
+
+impl Default for $name {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl $name {
+    pub fn new() -> Self {
+        $name {
+            memory: PROGRAM.to_vec(),
+            inputs: VecDeque::new(),
+            pc: 0,
+            rb: 0,
+            dirty: false,
+            fallback: None,
+        }
+    }
+
+    pub fn inputs(mut self, inputs: &[Word]) -> Self {
+        self.inputs.extend(inputs);
+        self
+    }
+
+    pub fn push_input(&mut self, input: Word) {
+        match self.fallback.as_mut() {
+            Some(ic) => ic.push_input(input),
+            None => self.inputs.push_back(input),
+        }
+    }
+
+    /// Whether execution has been handed over to the interpreter.
+    pub fn interpreted(&self) -> bool {
+        self.fallback.is_some()
+    }
+
+    fn rd(&self, idx: usize) -> Word {
+        self.memory.get(idx).cloned().unwrap_or(0)
+    }
+
+    fn rel(&self, offset: isize) -> usize {
+        usize::try_from(isize::try_from(self.rb).unwrap() + offset).unwrap()
+    }
+
+    fn wr(&mut self, idx: usize, w: Word) {
+        if idx >= self.memory.len() {
+            self.memory.resize(idx + 1, 0);
+        }
+        self.memory[idx] = w;
+        self.dirty |= $code;
+    }
+
+    fn interpret(&mut self, mut outs: Vec<Word>) -> (Vec<Word>, StopCondition) {
+        let inputs = self.inputs.drain(..).collect::<Vec<_>>();
+        let memory = std::mem::take(&mut self.memory);
+        let mut ic = Intcode::from_parts(memory, self.pc, self.rb).inputs(&inputs);
+
+        let (more, sc) = ic.run();
+        outs.extend(more);
+        self.fallback = Some(ic);
+        (outs, sc)
+    }
+
+    pub fn run(&mut self) -> (Vec<Word>, StopCondition) {
+        if let Some(ic) = self.fallback.as_mut() {
+            return ic.run();
+        }
+
+        let mut outs = Vec::new();
+
+        loop {
+            if self.dirty {
+                return self.interpret(outs);
+            }
+
+            match self.pc {
+"#;
+
+const FOOTER: &str = r#"                _ => return self.interpret(outs),
+            }
+        }
+    }
+}
+"#;
+
+#[cfg(all(test, feature = "transpiled"))]
+mod tests {
+    use crate::intcode::{Intcode, StopCondition};
+
+    #[allow(dead_code, unused_mut, clippy::all)]
+    mod day5 {
+        include!(concat!(env!("OUT_DIR"), "/day5.rs"));
+    }
+    #[allow(dead_code, unused_mut, clippy::all)]
+    mod day9 {
+        include!(concat!(env!("OUT_DIR"), "/day9.rs"));
+    }
+    #[allow(dead_code, unused_mut, clippy::all)]
+    mod day19 {
+        include!(concat!(env!("OUT_DIR"), "/day19.rs"));
+    }
+
+    #[test]
+    fn day5_falls_back() {
+        for &input in [1, 5].iter() {
+            let mut compiled = day5::Day5::new().inputs(&[input]);
+            let expected = Intcode::new(include_str!("../../res/5.txt"))
+                .inputs(&[input])
+                .run();
+
+            assert_eq!(compiled.run(), expected);
+            assert!(compiled.interpreted());
+        }
+    }
+
+    #[test]
+    fn day9() {
+        for &input in [1, 2].iter() {
+            let mut compiled = day9::Day9::new().inputs(&[input]);
+            let expected = Intcode::new(include_str!("../../res/9.txt"))
+                .inputs(&[input])
+                .run();
+
+            assert_eq!(compiled.run(), expected);
+            assert!(!compiled.interpreted());
+        }
+    }
+
+    #[test]
+    fn day19() {
+        for y in 0..50 {
+            for x in 0..50 {
+                let mut compiled = day19::Day19::new().inputs(&[x, y]);
+                let expected = Intcode::new(include_str!("../../res/19.txt"))
+                    .inputs(&[x, y])
+                    .run();
+
+                assert_eq!(compiled.run(), expected);
+                assert!(!compiled.interpreted());
+            }
+        }
+
+        let mut compiled = day19::Day19::new();
+        assert_eq!(compiled.run(), (vec![], StopCondition::NeedInput));
+        compiled.push_input(0);
+        compiled.push_input(0);
+        assert_eq!(compiled.run(), (vec![1], StopCondition::Halt));
+    }
+}
@@ -1,4 +1,13 @@
+pub mod gen;
+pub mod grid;
+pub mod input;
 pub mod intcode;
+pub mod manifest;
+pub mod ocr;
+pub mod parse;
+pub mod pathfinding;
+pub mod rng;
+pub mod solution;
 
 pub mod day1;
 pub mod day10;
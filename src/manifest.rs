@@ -0,0 +1,255 @@
+use crate::input;
+use crate::solution::{self, Entry};
+
+use std::fmt;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+/// The expected answers for one input file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expected {
+    pub day: u32,
+    pub input: PathBuf,
+    /// `None` leaves the part unchecked.
+    pub answers: [Option<String>; 2],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestError {
+    pub path: PathBuf,
+    pub line: usize,
+    pub msg: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Fail {
+        expected: String,
+    },
+    /// The day has no entry in the manifest.
+    Missing,
+    /// The input couldn't be read or the solver panicked.
+    Error(String),
+}
+
+/// The result of checking one part of one input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub day: u32,
+    pub part: u32,
+    pub input: Option<PathBuf>,
+    pub answer: Option<String>,
+    pub status: Status,
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.path.display(), self.line, self.msg)
+    }
+}
+
+/// Parses a manifest. Each line holds a day, an input path relative to the manifest and the
+/// answers to both parts, separated by whitespace; `-` leaves a part unchecked.
+///
+/// ```text
+/// # day  input    part 1  part 2
+/// 12     12.txt   7202    537881600740876
+/// 18     18.txt   4420    -
+/// ```
+pub fn parse(src: &str, path: &Path) -> Result<Vec<Expected>, ManifestError> {
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut entries = Vec::new();
+
+    for (i, raw) in src.lines().enumerate() {
+        let err = |msg: String| ManifestError {
+            path: path.to_path_buf(),
+            line: i + 1,
+            msg,
+        };
+
+        let line = raw.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.len() != 4 {
+            return Err(err(format!(
+                "expected `day input part1 part2`, found `{}`",
+                line
+            )));
+        }
+
+        let day = fields[0]
+            .parse()
+            .ok()
+            .filter(|&day| solution::lookup(day).is_some())
+            .ok_or_else(|| err(format!("no solution for day `{}`", fields[0])))?;
+        let answer = |field: &str| match field {
+            "-" => None,
+            _ => Some(field.to_string()),
+        };
+
+        entries.push(Expected {
+            day,
+            input: base.join(fields[1]),
+            answers: [answer(fields[2]), answer(fields[3])],
+        });
+    }
+
+    Ok(entries)
+}
+
+pub fn load(path: &Path) -> Result<Vec<Expected>, ManifestError> {
+    let src = fs::read_to_string(path).map_err(|e| ManifestError {
+        path: path.to_path_buf(),
+        line: 0,
+        msg: e.to_string(),
+    })?;
+    parse(&src, path)
+}
+
+fn solve(entry: &Entry, part: u32, input: &str) -> Result<Option<String>, String> {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        if part == 1 {
+            Some(entry.part_1(input))
+        } else {
+            entry.part_2(input)
+        }
+    }))
+    .map_err(|_| "solver panicked".to_string())
+    .and_then(|answer| answer.transpose().map_err(|e| e.to_string()))
+}
+
+/// Runs every registered day against its manifest entries, in day order. Days without an
+/// entry get a `Missing` check for both parts.
+pub fn verify(manifest: &[Expected]) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    for entry in solution::registry() {
+        let expected = manifest
+            .iter()
+            .filter(|e| e.day == entry.day)
+            .collect::<Vec<_>>();
+
+        if expected.is_empty() {
+            for part in 1..=2 {
+                checks.push(Check {
+                    day: entry.day,
+                    part,
+                    input: None,
+                    answer: None,
+                    status: Status::Missing,
+                });
+            }
+            continue;
+        }
+
+        for e in expected {
+            let input = input::read(&e.input).map_err(|err| err.to_string());
+
+            for (part, want) in (1..=2).zip(e.answers.iter()) {
+                let want = match want {
+                    Some(want) => want,
+                    None => continue,
+                };
+
+                let (answer, status) = match input.clone().and_then(|i| solve(&entry, part, &i)) {
+                    Ok(Some(answer)) if &answer == want => (Some(answer), Status::Pass),
+                    Ok(answer) => (
+                        answer,
+                        Status::Fail {
+                            expected: want.clone(),
+                        },
+                    ),
+                    Err(err) => (None, Status::Error(err)),
+                };
+
+                checks.push(Check {
+                    day: entry.day,
+                    part,
+                    input: Some(e.input.clone()),
+                    answer,
+                    status,
+                });
+            }
+        }
+    }
+
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_entries() {
+        let path = Path::new("res/answers.txt");
+        let entries = parse("# comment\n12 12.txt 7202 -\n\n", path).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![Expected {
+                day: 12,
+                input: PathBuf::from("res/12.txt"),
+                answers: [Some("7202".to_string()), None],
+            }]
+        );
+
+        let err = |src| parse(src, path).unwrap_err().to_string();
+        assert_eq!(
+            err("1 1.txt 2\n"),
+            "res/answers.txt:1: expected `day input part1 part2`, found `1 1.txt 2`"
+        );
+        assert_eq!(
+            err("\n42 1.txt 1 2"),
+            "res/answers.txt:2: no solution for day `42`"
+        );
+    }
+
+    #[test]
+    fn checks_answers() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("res");
+        let manifest = parse(
+            "1 1.txt 3212842 4816402\n4 4.txt 1873 0\n6 6-ex0.txt 42 -\n7 none.txt 1 -",
+            &dir.join("answers.txt"),
+        )
+        .unwrap();
+        let checks = verify(&manifest);
+        let status = |day, part| {
+            checks
+                .iter()
+                .find(|c| (c.day, c.part) == (day, part))
+                .map(|c| c.status.clone())
+        };
+
+        assert_eq!(status(1, 1), Some(Status::Pass));
+        assert_eq!(status(1, 2), Some(Status::Pass));
+        assert_eq!(status(4, 1), Some(Status::Pass));
+        assert_eq!(
+            status(4, 2),
+            Some(Status::Fail {
+                expected: "0".to_string()
+            })
+        );
+        assert_eq!(status(6, 1), Some(Status::Pass));
+        assert_eq!(status(6, 2), None);
+        assert!(matches!(status(7, 1), Some(Status::Error(_))));
+        assert_eq!(status(2, 1), Some(Status::Missing));
+        assert_eq!(checks.len(), 2 + 2 + 1 + 1 + 18 * 2);
+    }
+
+    #[test]
+    fn answers_file_covers_every_day() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("res/answers.txt");
+        let manifest = load(&path).unwrap();
+
+        for entry in solution::registry() {
+            assert!(manifest.iter().any(|e| e.day == entry.day), "{}", entry.day);
+        }
+        assert!(manifest.iter().all(|e| e.input.exists()));
+    }
+}
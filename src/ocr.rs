@@ -0,0 +1,161 @@
+use crate::grid::Grid;
+
+use std::error::Error;
+use std::fmt;
+
+const HEIGHT: usize = 6;
+/// Columns from the start of one letter to the start of the next.
+const PITCH: usize = 5;
+
+/// The capital letters of the block font, one string per row.
+const FONT: [(char, [&str; HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// A glyph that isn't a letter of the font, with the image column it starts at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OcrError {
+    pub col: usize,
+    pub glyph: String,
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown glyph at column {}:\n{}", self.col, self.glyph)
+    }
+}
+
+impl Error for OcrError {}
+
+/// Reads the letters lit up in `image`. Letters are told apart by the blank columns between
+/// them, so the image may have any margin around the text. Letters as wide as the pitch, like
+/// `Y`, leave no blank column and may touch the next one.
+pub fn read(image: &Grid<bool>) -> Result<String, OcrError> {
+    let lit_rows = (0..image.height())
+        .filter(|&y| image.row(y).contains(&true))
+        .collect::<Vec<_>>();
+    let rows = match (lit_rows.first(), lit_rows.last()) {
+        (Some(&top), Some(&bottom)) => top..=bottom,
+        _ => return Ok(String::new()),
+    };
+    let lit = |x: usize| image.column(x).any(|&p| p);
+    let glyph = |start: usize, end: usize| {
+        rows.clone()
+            .map(|y| {
+                image.row(y)[start..end]
+                    .iter()
+                    .map(|&p| if p { '#' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut text = String::new();
+    let mut x = 0;
+
+    while x < image.width() {
+        if !lit(x) {
+            x += 1;
+            continue;
+        }
+
+        let start = x;
+        let letter = FONT.iter().find(|(_, rows)| {
+            let end = start + rows[0].len();
+            end <= image.width()
+                && (end - start >= PITCH || end == image.width() || !lit(end))
+                && rows[..] == glyph(start, end)[..]
+        });
+
+        match letter {
+            Some(&(c, rows)) => {
+                text.push(c);
+                x = start + rows[0].len();
+            }
+            None => {
+                while x < image.width() && lit(x) {
+                    x += 1;
+                }
+                return Err(OcrError {
+                    col: start,
+                    glyph: glyph(start, x).join("\n"),
+                });
+            }
+        }
+    }
+
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(rows: &[&str]) -> Grid<bool> {
+        Grid::parse(&rows.join("\n"), |c| Some(c == '#')).unwrap()
+    }
+
+    #[test]
+    fn reads_every_letter() {
+        let margin = ["......"; HEIGHT + 2];
+        let rows = (0..HEIGHT + 2)
+            .map(|y| {
+                let letters = FONT.iter().map(|(_, glyph)| match y {
+                    1..=HEIGHT => glyph[y - 1].to_string(),
+                    _ => ".".repeat(glyph[0].len()),
+                });
+                format!("{}{}.", margin[y], letters.collect::<Vec<_>>().join("."))
+            })
+            .collect::<Vec<_>>();
+        let rows = rows.iter().map(String::as_str).collect::<Vec<_>>();
+
+        assert_eq!(read(&image(&rows)), Ok("ABCEFGHJKLOPRSUYZ".to_string()));
+        assert_eq!(read(&image(&["...."; HEIGHT])), Ok(String::new()));
+    }
+
+    #[test]
+    fn reads_letters_touching_a_y() {
+        let font = |c| FONT.iter().find(|&&(l, _)| l == c).unwrap().1;
+        let rows = (0..HEIGHT)
+            .map(|y| format!("{}.{}{}.", font('A')[y], font('Y')[y], font('Z')[y]))
+            .collect::<Vec<_>>();
+        let rows = rows.iter().map(String::as_str).collect::<Vec<_>>();
+
+        assert_eq!(read(&image(&rows)), Ok("AYZ".to_string()));
+    }
+
+    #[test]
+    fn points_at_unknown_glyphs() {
+        let err = read(&image(&[
+            "####.#..#",
+            "#....#..#",
+            "###..####",
+            "#....#..#",
+            "#....#..#",
+            "#....##.#",
+        ]))
+        .unwrap_err();
+
+        assert_eq!(err.col, 5);
+        assert_eq!(
+            err.to_string(),
+            "unknown glyph at column 5:\n#..#\n#..#\n####\n#..#\n#..#\n##.#"
+        );
+    }
+}
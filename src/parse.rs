@@ -0,0 +1,70 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Bad puzzle input, with the 1-based position of the offending text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub col: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.msg)
+    }
+}
+
+impl Error for ParseError {}
+
+impl ParseError {
+    /// An error about `part`, which must be a slice of `src`; its position is worked out
+    /// from where the slice starts.
+    pub fn at<S: Into<String>>(src: &str, part: &str, msg: S) -> ParseError {
+        let offset = (part.as_ptr() as usize)
+            .checked_sub(src.as_ptr() as usize)
+            .filter(|&offset| offset <= src.len())
+            .expect("`part` is not a slice of `src`");
+        let before = &src[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        ParseError {
+            line: before.matches('\n').count() + 1,
+            col: before[line_start..].chars().count() + 1,
+            msg: msg.into(),
+        }
+    }
+
+    /// An error about the end of `src`, e.g. for missing lines.
+    pub fn at_end<S: Into<String>>(src: &str, msg: S) -> ParseError {
+        ParseError::at(src, &src[src.len()..], msg)
+    }
+}
+
+/// Parses `part`, a slice of `src`, as a number.
+pub fn number<T: FromStr>(src: &str, part: &str) -> Result<T, ParseError> {
+    part.parse()
+        .map_err(|_| ParseError::at(src, part, format!("invalid number `{}`", part)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions() {
+        let src = "12\nab,3x\n";
+        let field = src.lines().nth(1).unwrap().split(',').nth(1).unwrap();
+
+        assert_eq!(number::<u32>(src, &src[..2]), Ok(12));
+        assert_eq!(
+            number::<u32>(src, field).unwrap_err().to_string(),
+            "2:4: invalid number `3x`"
+        );
+        assert_eq!(
+            ParseError::at_end(src, "expected more").to_string(),
+            "3:1: expected more"
+        );
+    }
+}
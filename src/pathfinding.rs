@@ -0,0 +1,207 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+/// What a search found: the distance to every node it reached and the way there.
+///
+/// A search stops as soon as it takes a goal node off its queue, so only the distances of
+/// the goal and of nodes settled before it are final; a search without a goal settles
+/// everything reachable.
+#[derive(Debug, Clone)]
+pub struct Paths<N> {
+    start: N,
+    dist: HashMap<N, usize>,
+    parent: HashMap<N, N>,
+    goal: Option<N>,
+}
+
+impl<N: Eq + Hash + Clone> Paths<N> {
+    fn new(start: N) -> Paths<N> {
+        let mut dist = HashMap::new();
+        dist.insert(start.clone(), 0);
+
+        Paths {
+            start,
+            dist,
+            parent: HashMap::new(),
+            goal: None,
+        }
+    }
+
+    /// The goal node the search stopped at, if it found one.
+    pub fn goal(&self) -> Option<&N> {
+        self.goal.as_ref()
+    }
+
+    pub fn distance(&self, to: &N) -> Option<usize> {
+        self.dist.get(to).copied()
+    }
+
+    pub fn distances(&self) -> impl Iterator<Item = (&N, usize)> {
+        self.dist.iter().map(|(n, &d)| (n, d))
+    }
+
+    /// The nodes from the start to `to`, both included.
+    pub fn path(&self, to: &N) -> Option<Vec<N>> {
+        if !self.dist.contains_key(to) {
+            return None;
+        }
+
+        let mut path = vec![to.clone()];
+        while let Some(parent) = self.parent.get(path.last().unwrap()) {
+            path.push(parent.clone());
+        }
+        path.reverse();
+
+        debug_assert!(path[0] == self.start);
+        Some(path)
+    }
+
+    /// Records `next` as reached through `from` if that's shorter than any known way.
+    fn relax(&mut self, from: &N, next: &N, dist: usize) -> bool {
+        if self.dist.get(next).is_some_and(|&d| d <= dist) {
+            return false;
+        }
+        self.dist.insert(next.clone(), dist);
+        self.parent.insert(next.clone(), from.clone());
+        true
+    }
+}
+
+/// Breadth-first search over a graph whose edges all cost one step.
+pub fn bfs<N, S, I, G>(start: N, mut successors: S, mut goal: G) -> Paths<N>
+where
+    N: Eq + Hash + Clone,
+    S: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+    G: FnMut(&N) -> bool,
+{
+    let mut paths = Paths::new(start.clone());
+    let mut q = VecDeque::new();
+    q.push_back(start);
+
+    while let Some(node) = q.pop_front() {
+        if goal(&node) {
+            paths.goal = Some(node);
+            break;
+        }
+
+        let dist = paths.dist[&node] + 1;
+        for next in successors(&node) {
+            if !paths.dist.contains_key(&next) {
+                paths.relax(&node, &next, dist);
+                q.push_back(next);
+            }
+        }
+    }
+
+    paths
+}
+
+/// Dijkstra's algorithm, with `successors` returning each neighbour and the cost of the
+/// edge to it.
+pub fn dijkstra<N, S, I, G>(start: N, successors: S, goal: G) -> Paths<N>
+where
+    N: Eq + Hash + Clone,
+    S: FnMut(&N) -> I,
+    I: IntoIterator<Item = (N, usize)>,
+    G: FnMut(&N) -> bool,
+{
+    astar(start, successors, |_| 0, goal)
+}
+
+/// A* search, guided by a `heuristic` that must never overestimate the distance left to a
+/// goal.
+pub fn astar<N, S, I, H, G>(start: N, mut successors: S, mut heuristic: H, mut goal: G) -> Paths<N>
+where
+    N: Eq + Hash + Clone,
+    S: FnMut(&N) -> I,
+    I: IntoIterator<Item = (N, usize)>,
+    H: FnMut(&N) -> usize,
+    G: FnMut(&N) -> bool,
+{
+    let mut paths = Paths::new(start.clone());
+    // The heap refers to nodes by index so that they don't need to be ordered.
+    let mut heap = BinaryHeap::new();
+    let mut nodes = vec![];
+
+    heap.push(Reverse((heuristic(&start), 0, nodes.len())));
+    nodes.push(start);
+
+    while let Some(Reverse((_, dist, i))) = heap.pop() {
+        let node = nodes[i].clone();
+
+        // Skip entries that were superseded by a shorter way to the same node
+        if dist > paths.dist[&node] {
+            continue;
+        }
+        if goal(&node) {
+            paths.goal = Some(node);
+            break;
+        }
+
+        for (next, cost) in successors(&node) {
+            if paths.relax(&node, &next, dist + cost) {
+                heap.push(Reverse((
+                    dist + cost + heuristic(&next),
+                    dist + cost,
+                    nodes.len(),
+                )));
+                nodes.push(next);
+            }
+        }
+    }
+
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn searches_a_grid() {
+        // Walls in a 5x5 room, with a single gap in the middle one
+        let wall = |(x, y): (i32, i32)| x == 2 && y != 4;
+        let open = |&(x, y): &(i32, i32)| {
+            vec![(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+                .into_iter()
+                .filter(|&(x, y)| (0..5).contains(&x) && (0..5).contains(&y))
+                .filter(move |&p| !wall(p))
+        };
+
+        let paths = bfs((0, 0), open, |&p| p == (4, 0));
+        assert_eq!(paths.goal(), Some(&(4, 0)));
+        assert_eq!(paths.distance(&(4, 0)), Some(12));
+
+        let path = paths.path(&(4, 0)).unwrap();
+        assert_eq!((path.len(), path[0], path[6]), (13, (0, 0), (2, 4)));
+
+        let all = bfs((0, 0), open, |_| false);
+        assert_eq!(all.goal(), None);
+        assert_eq!(all.distances().count(), 21);
+        assert_eq!(all.distances().map(|(_, d)| d).max(), Some(12));
+
+        let weighted = |p: &(i32, i32)| open(p).map(|n| (n, 1)).collect::<Vec<_>>();
+        let manhattan = |&(x, y): &(i32, i32)| ((4 - x).abs() + y.abs()) as usize;
+        let astar = astar((0, 0), weighted, manhattan, |&p| p == (4, 0));
+        assert_eq!(astar.distance(&(4, 0)), Some(12));
+        assert!(astar.distances().count() <= all.distances().count());
+    }
+
+    #[test]
+    fn dijkstra_takes_cheap_detours() {
+        let edges = |&n: &char| match n {
+            'a' => vec![('b', 7), ('c', 2)],
+            'c' => vec![('b', 3), ('d', 9)],
+            'b' => vec![('d', 1)],
+            _ => vec![],
+        };
+
+        let paths = dijkstra('a', edges, |&n| n == 'd');
+        assert_eq!(paths.distance(&'d'), Some(6));
+        assert_eq!(paths.path(&'d'), Some(vec!['a', 'c', 'b', 'd']));
+        assert_eq!(paths.path(&'e'), None);
+        assert_eq!(dijkstra('a', edges, |&n| n == 'e').goal(), None);
+    }
+}
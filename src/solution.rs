@@ -0,0 +1,148 @@
+use crate::parse::ParseError;
+use crate::*;
+
+use std::error;
+use std::fmt;
+
+/// Why a day has no answer for its input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The input is malformed.
+    Parse(ParseError),
+    /// The input is well-formed, but the puzzle it describes can't be solved.
+    Unsolvable(String),
+}
+
+impl Error {
+    pub fn unsolvable<S: Into<String>>(msg: S) -> Error {
+        Error::Unsolvable(msg.into())
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Error {
+        Error::Parse(e)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(e) => e.fmt(f),
+            Error::Unsolvable(msg) => write!(f, "no solution: {}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// A day's puzzle, solved from the raw text of its input.
+pub trait Solution {
+    type Part1: fmt::Display;
+    type Part2: fmt::Display;
+
+    fn part_1(input: &str) -> Result<Self::Part1, Error>;
+
+    /// `None` for days whose second part isn't solved.
+    fn part_2(input: &str) -> Option<Result<Self::Part2, Error>>;
+}
+
+/// A registered day, with its answers already formatted.
+#[derive(Debug, Copy, Clone)]
+pub struct Entry {
+    pub day: u32,
+    part_1: fn(&str) -> Result<String, Error>,
+    part_2: fn(&str) -> Option<Result<String, Error>>,
+}
+
+impl Entry {
+    fn new<S: Solution>(day: u32) -> Entry {
+        Entry {
+            day,
+            part_1: |input| S::part_1(input).map(|answer| answer.to_string()),
+            part_2: |input| S::part_2(input).map(|answer| answer.map(|a| a.to_string())),
+        }
+    }
+
+    pub fn part_1(&self, input: &str) -> Result<String, Error> {
+        (self.part_1)(input)
+    }
+
+    pub fn part_2(&self, input: &str) -> Option<Result<String, Error>> {
+        (self.part_2)(input)
+    }
+}
+
+/// Every solved day, in order.
+pub fn registry() -> Vec<Entry> {
+    vec![
+        Entry::new::<day1::Puzzle>(1),
+        Entry::new::<day2::Puzzle>(2),
+        Entry::new::<day3::Puzzle>(3),
+        Entry::new::<day4::Puzzle>(4),
+        Entry::new::<day5::Puzzle>(5),
+        Entry::new::<day6::Puzzle>(6),
+        Entry::new::<day7::Puzzle>(7),
+        Entry::new::<day8::Puzzle>(8),
+        Entry::new::<day9::Puzzle>(9),
+        Entry::new::<day10::Puzzle>(10),
+        Entry::new::<day11::Puzzle>(11),
+        Entry::new::<day12::Puzzle>(12),
+        Entry::new::<day13::Puzzle>(13),
+        Entry::new::<day14::Puzzle>(14),
+        Entry::new::<day15::Puzzle>(15),
+        Entry::new::<day16::Puzzle>(16),
+        Entry::new::<day17::Puzzle>(17),
+        Entry::new::<day18::Puzzle>(18),
+        Entry::new::<day19::Puzzle>(19),
+        Entry::new::<day20::Puzzle>(20),
+        Entry::new::<day21::Puzzle>(21),
+        Entry::new::<day22::Puzzle>(22),
+    ]
+}
+
+pub fn lookup(day: u32) -> Option<Entry> {
+    registry().into_iter().find(|entry| entry.day == day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_every_day() {
+        let days = registry().iter().map(|e| e.day).collect::<Vec<_>>();
+
+        assert_eq!(days, (1..=22).collect::<Vec<_>>());
+        assert!(lookup(0).is_none());
+        assert!(lookup(23).is_none());
+    }
+
+    #[test]
+    fn runs_days_from_raw_input() {
+        let run = |day, input| {
+            let entry = lookup(day).unwrap();
+            (entry.part_1(input), entry.part_2(input))
+        };
+
+        assert_eq!(
+            run(2, include_str!("../res/2.txt")),
+            (Ok("5534943".to_string()), Some(Ok("7603".to_string())))
+        );
+        assert_eq!(
+            run(4, include_str!("../res/4.txt")),
+            (Ok("1873".to_string()), Some(Ok("1264".to_string())))
+        );
+        assert_eq!(
+            run(12, include_str!("../res/12.txt")),
+            (
+                Ok("7202".to_string()),
+                Some(Ok("537881600740876".to_string()))
+            )
+        );
+        assert_eq!(
+            run(22, include_str!("../res/22.txt")),
+            (Ok("6638".to_string()), None)
+        );
+    }
+}